@@ -4,16 +4,15 @@
 
 use std::vec;
 
-use darling::{
-    ast,
-    util::{Ignored, Override},
-    FromDeriveInput, FromField, FromMeta, ToTokens,
-};
+use darling::{ast, util::Override, FromDeriveInput, FromField, FromMeta, FromVariant, ToTokens};
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, GenericArgument, Ident, Path, PathArguments, Type};
+use syn::{
+    ext::IdentExt, parse_macro_input, parse_quote, GenericArgument, Ident, Path, PathArguments,
+    Type,
+};
 
-#[proc_macro_derive(LayeredConf, attributes(layered, clap, doc))]
+#[proc_macro_derive(LayeredConf, attributes(layered, clap))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input);
     let conf_struct = LayeredConfStruct::from_derive_input(&input).expect("Wrong options");
@@ -27,48 +26,52 @@ pub fn derive(input: TokenStream) -> TokenStream {
 #[darling(
     attributes(layered),
     forward_attrs(clap, serde, doc),
-    supports(struct_named)
+    supports(struct_named, enum_named, enum_unit)
 )]
 struct LayeredConfStruct {
     ident: Ident,
-    data: ast::Data<Ignored, LayeredConfField>,
+    vis: syn::Visibility,
+    generics: syn::Generics,
+    data: ast::Data<LayeredConfVariant, LayeredConfField>,
     attrs: Vec<syn::Attribute>,
 
     #[darling(default)]
     subconfig: bool,
     #[darling(default)]
     default: bool,
+    #[darling(default)]
+    validate_with: Option<Path>,
+    #[darling(default)]
+    unset_flag: bool,
 }
 
 impl LayeredConfStruct {
     fn is_option(&self, ty: &Type) -> bool {
-        match ty {
-            Type::Path(path) => match path.path.segments.first() {
-                Some(seg) => seg.ident == "Option",
-                _ => false,
-            },
-            _ => false,
-        }
+        self.extract_type(ty).is_some()
     }
 
+    /// The `T` of an `Option<T>`, written as `Option` or by its full path
     fn extract_type(&self, ty: &Type) -> Option<Type> {
         fn path_is_option(path: &Path) -> bool {
-            path.leading_colon.is_none()
-                && path.segments.len() == 1
-                && path.segments.first().unwrap().ident == "Option"
+            let idents = path
+                .segments
+                .iter()
+                .map(|seg| seg.ident.to_string())
+                .collect::<Vec<_>>();
+            match idents.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["Option"] => path.leading_colon.is_none(),
+                ["std" | "core", "option", "Option"] => true,
+                _ => false,
+            }
         }
         match ty {
             Type::Path(tp) if tp.qself.is_none() && path_is_option(&tp.path) => {
-                if let Some(seg) = tp.path.segments.first() {
-                    match &seg.arguments {
-                        PathArguments::AngleBracketed(params) => match params.args.first() {
-                            Some(GenericArgument::Type(ty)) => Some(ty.clone()),
-                            _ => None,
-                        },
+                match &tp.path.segments.last()?.arguments {
+                    PathArguments::AngleBracketed(params) => match params.args.first() {
+                        Some(GenericArgument::Type(ty)) => Some(ty.clone()),
                         _ => None,
-                    }
-                } else {
-                    None
+                    },
+                    _ => None,
                 }
             }
             _ => None,
@@ -79,12 +82,227 @@ impl LayeredConfStruct {
         format_ident!("{}Layer", self.ident)
     }
 
+    /// The type parameter a field's type is, if it's just one of the struct's
+    fn type_param<'a>(&self, ty: &'a Type) -> Option<&'a Ident> {
+        match ty {
+            Type::Path(path) if path.qself.is_none() => {
+                let ident = path.path.get_ident()?;
+                self.generics
+                    .type_params()
+                    .any(|param| &param.ident == ident)
+                    .then_some(ident)
+            }
+            _ => None,
+        }
+    }
+
+    /// How a `subconfig` field holds its subconfig, and the subconfig's type
+    fn subconfig(&self, f: &LayeredConfField) -> Option<(SubconfigKind, Type)> {
+        if !f.subconfig {
+            return None;
+        }
+        if let Some(ty) = self.extract_type(&f.ty) {
+            return Some((SubconfigKind::Optional, ty));
+        }
+        let seg = match &f.ty {
+            Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+            _ => None,
+        };
+        let type_args = match seg.map(|seg| &seg.arguments) {
+            Some(PathArguments::AngleBracketed(params)) => params
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        Some(
+            match (
+                seg.map(|seg| seg.ident.to_string()).as_deref(),
+                &type_args[..],
+            ) {
+                (Some("Vec"), [ty]) => (SubconfigKind::Vec, (*ty).clone()),
+                (Some("HashMap" | "BTreeMap"), [_, ty, ..]) => (SubconfigKind::Map, (*ty).clone()),
+                _ => (SubconfigKind::Single, f.ty.clone()),
+            },
+        )
+    }
+
+    /// Whether a field holds a single subconfig, whose Layer is flattened into this one
+    fn flattened(&self, f: &LayeredConfField) -> bool {
+        matches!(
+            self.subconfig(f),
+            Some((SubconfigKind::Single | SubconfigKind::Optional, _))
+        )
+    }
+
+    /// The Layer type of a `Vec` or map of subconfigs, holding their Layers
+    fn collection_layer_type(&self, f: &LayeredConfField) -> Type {
+        let (kind, sub_ty) = self.subconfig(f).expect("Should be a subconfig");
+        let layer_ty = self.subconfig_layer_type(&sub_ty);
+        let mut ty = f.ty.clone();
+        if let Type::Path(path) = &mut ty {
+            if let Some(PathArguments::AngleBracketed(params)) =
+                path.path.segments.last_mut().map(|seg| &mut seg.arguments)
+            {
+                let index = match kind {
+                    SubconfigKind::Map => 1,
+                    _ => 0,
+                };
+                if let Some(GenericArgument::Type(item)) = params
+                    .args
+                    .iter_mut()
+                    .filter(|arg| matches!(arg, GenericArgument::Type(_)))
+                    .nth(index)
+                {
+                    *item = parse_quote! { #layer_ty };
+                }
+            }
+        }
+        ty
+    }
+
+    /// The Layer type of a subconfig field's type, through `LayeredConfSolid` so any path or
+    /// alias works
+    ///
+    /// Type parameters use `B::Layer`, so serde bounds the Layer rather than the Config.
+    fn subconfig_layer_type(&self, ty: &Type) -> proc_macro2::TokenStream {
+        match self.type_param(ty) {
+            Some(param) => quote! { #param::Layer },
+            None => quote! { <#ty as layeredconf::LayeredConfSolid>::Layer },
+        }
+    }
+
+    /// The struct's generics, with the bounds needed by subconfigs whose type is a type
+    /// parameter
+    fn layer_generics(&self) -> syn::Generics {
+        let mut generics = self.generics.clone();
+        for (_, ty) in self.fields().into_iter().filter_map(|f| self.subconfig(f)) {
+            if let Some(param) = self.type_param(&ty) {
+                let predicates = &mut generics.make_where_clause().predicates;
+                predicates.push(parse_quote! {
+                    #param: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff
+                });
+                predicates.push(parse_quote! {
+                    #param::Layer: layeredconf::LayeredConfSubLayer<#param>
+                });
+            }
+        }
+        generics
+    }
+
+    /// The Layer's generics, also requiring the Config and Layer to be deserializable
+    /// when there are type parameters, as `LayeredConfSolid` and `LayeredConfLayer` need
+    fn owned_generics(&self) -> syn::Generics {
+        let ident = &self.ident;
+        let layer_ident = self.layer_ident();
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+
+        let mut generics = self.layer_generics();
+        if self.generics.type_params().next().is_some() {
+            let predicates = &mut generics.make_where_clause().predicates;
+            predicates.push(parse_quote! { #ident #ty_generics: serde::de::DeserializeOwned });
+            predicates
+                .push(parse_quote! { #layer_ident #ty_generics: serde::de::DeserializeOwned });
+        }
+        generics
+    }
+
     fn fields(&self) -> Vec<&LayeredConfField> {
-        self.data
-            .as_ref()
-            .take_struct()
-            .expect("Should never be enum")
-            .fields
+        let variants = match &self.data {
+            ast::Data::Struct(fields) => return fields.iter().collect(),
+            ast::Data::Enum(variants) => variants,
+        };
+
+        // An enum's Layer has the fields of all its variants, with variants sharing the
+        // fields they have with the same name
+        let mut fields: Vec<&LayeredConfField> = vec![];
+        for f in variants.iter().flat_map(|v| v.fields.iter()) {
+            match fields.iter().find(|field| field.ident == f.ident) {
+                Some(field) => {
+                    if field.ty.to_token_stream().to_string() != f.ty.to_token_stream().to_string()
+                    {
+                        panic!(
+                            "Fields named {} in several variants must have the same type",
+                            f.ident.as_ref().expect("Should have an ident"),
+                        );
+                    }
+                }
+                None => fields.push(f),
+            }
+        }
+        fields
+    }
+
+    /// Checks for fields the derive can't generate code for, with an error at the field
+    fn check(&self) -> syn::Result<()> {
+        let variant_fields = self
+            .variants()
+            .into_iter()
+            .flatten()
+            .flat_map(|v| v.fields.iter());
+        for f in variant_fields {
+            if f.subconfig || f.load_config {
+                return Err(syn::Error::new_spanned(
+                    &f.ident,
+                    "Fields of enum variants can't be subconfigs or load_config",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn variants(&self) -> Option<Vec<&LayeredConfVariant>> {
+        self.data.as_ref().take_enum()
+    }
+
+    /// The unit enum of an enum's variants, which its Layer selects one of by its tag
+    fn variant_ident(&self) -> Ident {
+        format_ident!("{}Variant", self.layer_ident())
+    }
+
+    /// Initialises an enum's Layer with no variant selected
+    fn variant_none(&self) -> Option<proc_macro2::TokenStream> {
+        self.variants().map(|_| quote! { _variant: None, })
+    }
+
+    /// The key serde names a field by, from its `#[serde(rename = "...")]` or the struct's
+    /// `#[serde(rename_all = "...")]`
+    ///
+    /// An enum's `rename_all` is for its variants, which its Layer's fields keep the names of.
+    fn key(&self, f: &LayeredConfField) -> String {
+        if let Some(syn::Lit::Str(rename)) = serde_attr(&f.attrs, "rename") {
+            return rename.value();
+        }
+        let name = f.ident.as_ref().expect("Named field").unraw().to_string();
+        match serde_attr(&self.attrs, "rename_all") {
+            Some(syn::Lit::Str(rule)) if self.variants().is_none() => {
+                rename_case(&name, &rule.value())
+            }
+            _ => name,
+        }
+    }
+
+    /// The other keys serde accepts for a field, from its `#[serde(alias = "...")]`s
+    fn aliases(&self, f: &LayeredConfField) -> Vec<String> {
+        serde_attrs(&f.attrs, "alias")
+            .into_iter()
+            .filter_map(|alias| match alias {
+                syn::Lit::Str(alias) => Some(alias.value()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The field naming an enum's variant, from its `#[serde(tag = "...")]`
+    fn tag(&self) -> String {
+        match serde_attr(&self.attrs, "tag") {
+            Some(syn::Lit::Str(tag)) => tag.value(),
+            _ => panic!("Enums need an internal tag, as #[serde(tag = \"...\")]"),
+        }
     }
 
     fn to_layer_tokens(&self) -> proc_macro2::TokenStream {
@@ -98,29 +316,20 @@ impl LayeredConfStruct {
                 let name = &f.ident;
                 let ty = &f.ty;
 
-                let attrs = f
-                    .attrs
-                    .iter()
-                    .map(|a| a.into_token_stream())
-                    .collect::<Vec<_>>();
+                let attrs = forward_attrs(&f.attrs);
 
                 let option = self.is_option(ty);
                 let subtype = if option { self.extract_type(ty) } else { None };
                 let subconfig = f.subconfig;
                 match (option, subconfig, subtype) {
-                    (true, false, _) => {
+                    (true, false, subtype) => {
+                        // Written as plain `Option` for clap, which only recognises that
                         quote! {
                             #[serde(default, skip_serializing_if = "Option::is_none")]
                             #(#attrs)*
-                            #name: #ty,
+                            #name: Option<#subtype>,
                         }
                     }
-                    (true, true, Some(_)) => {
-                        panic!("layered(subconfig) should not be wrapped in Option")
-                    }
-                    (true, true, None) => {
-                        panic!("Subtype not extracted {:?} {:?}", name, ty);
-                    }
                     (false, false, _) => {
                         quote! {
                             #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -128,53 +337,132 @@ impl LayeredConfStruct {
                             #name: Option<#ty>,
                         }
                     }
-                    (false, true, None) => {
-                        let ty_id = match &ty {
-                            Type::Path(path) => match path.path.segments.first() {
-                                Some(seg) => &seg.ident,
-                                _ => panic!("Can't find ident"),
-                            },
-                            _ => panic!("Can't find ident"),
-                        };
-                        let layer_ty = format_ident!("{}Layer", ty_id);
+                    (_, true, _) if !self.flattened(f) => {
+                        let layer_ty = self.collection_layer_type(f);
+                        let clap_skip = clap_attr(quote! { skip });
 
-                        let skip_serializing_if = quote! { #layer_ty::empty }.to_string();
+                        quote! {
+                            #[serde(default, skip_serializing_if = "Option::is_none")]
+                            #clap_skip
+                            #(#attrs)*
+                            #name: Option<#layer_ty>,
+                        }
+                    }
+                    (_, true, subtype) => {
+                        // An optional subconfig has the same Layer, which is `None` when
+                        // solidified if no Layer had its section or set any of its fields
+                        let layer_ty = self.subconfig_layer_type(subtype.as_ref().unwrap_or(ty));
+                        let skip_serializing_if = "layeredconf::absent_subconfig";
+                        let clap_flatten = clap_attr(quote! { flatten });
 
                         quote! {
                             #[serde(default, skip_serializing_if = #skip_serializing_if)]
-                            #[clap(flatten)]
+                            #clap_flatten
                             #(#attrs)*
                             #name: #layer_ty,
                         }
                     }
-                    (false, true, Some(..)) => {
-                        panic!("Subtype not extracted {:?} {:?}", name, ty);
-                    }
                 }
             })
             .collect::<Vec<_>>();
 
-        let container_attrs = self
+        // An enum's serde attributes are for its variants, which its Layer's are not
+        let attrs = self
             .attrs
             .iter()
-            .map(|a| a.into_token_stream())
+            .filter(|a| self.variants().is_none() || !a.path.is_ident("serde"))
+            .cloned()
             .collect::<Vec<_>>();
+        let container_attrs = forward_attrs(&attrs);
+
+        let clap_derive = match (cfg!(feature = "clap"), self.subconfig) {
+            (false, _) => None,
+            (true, true) => Some(quote! { clap::Args, }),
+            (true, false) => Some(quote! { clap::Parser, }),
+        };
 
-        let clap_derive = if self.subconfig {
-            quote! { clap::Args }
-        } else {
-            quote! { clap::Parser }
+        let unset_clap = match (self.unset_flag, self.subconfig) {
+            (true, true) => panic!("#[layered(unset_flag)] is only supported on the root Config"),
+            (false, _) => clap_attr(quote! { skip }),
+            (true, false) => clap_attr(quote! {
+                long = "unset",
+                value_name = "FIELD",
+                help = "Unset a field set by a lower layer, resetting it to its default"
+            }),
         };
 
+        let present_clap = clap_attr(quote! { skip });
+
+        let variant_field = self.variants().map(|_| {
+            let tag = self.tag();
+            let variant_ident = self.variant_ident();
+            let clap_skip = clap_attr(quote! { skip });
+            quote! {
+                #[serde(rename = #tag, default, skip_serializing_if = "Option::is_none")]
+                #clap_skip
+                _variant: Option<#variant_ident>,
+            }
+        });
+
+        // The Layer is as visible as the Config, so it can be named through `LayeredConfSolid`
+        // by Configs in other modules
+        let vis = &self.vis;
+        let generics = self.layer_generics();
+        let where_clause = &generics.where_clause;
+
         quote! {
-            #[derive(serde::Deserialize, serde::Serialize, #clap_derive, Clone, Debug)]
+            #[derive(serde::Deserialize, serde::Serialize, #clap_derive Clone, Debug)]
             #(#container_attrs)*
-            struct #layer_ident {
+            #vis struct #layer_ident #generics #where_clause {
                 #(#option_field_list)*
+                #variant_field
+                #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+                #unset_clap
+                _unset: Vec<String>,
+                // Whether the Layer had this section, even if it set none of its fields
+                #[serde(skip, default = "layeredconf::merge::present")]
+                #present_clap
+                _present: bool,
             }
         }
     }
 
+    /// The unit enum naming an enum's variants, deserialized from its tag as serde would
+    fn to_variant_tokens(&self) -> Option<proc_macro2::TokenStream> {
+        let variants = self.variants()?;
+
+        let variant_list = variants
+            .iter()
+            .map(|v| {
+                let ident = &v.ident;
+                let attrs = forward_attrs(&v.attrs);
+                quote! {
+                    #(#attrs)*
+                    #ident,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let rename_all = serde_attr(&self.attrs, "rename_all")
+            .map(|rename_all| quote! { #[serde(rename_all = #rename_all)] });
+
+        let vis = &self.vis;
+        let variant_ident = self.variant_ident();
+        let doc = format!(
+            "The variants of `{}`, which its Layer selects by its tag",
+            self.ident
+        );
+
+        Some(quote! {
+            #[doc = #doc]
+            #[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+            #rename_all
+            #vis enum #variant_ident {
+                #(#variant_list)*
+            }
+        })
+    }
+
     fn to_impl_layered_conf_tokens(&self) -> proc_macro2::TokenStream {
         let ident = &self.ident;
         let layer_ident = self.layer_ident();
@@ -197,270 +485,1081 @@ impl LayeredConfStruct {
             .collect::<Vec<_>>();
 
         let default_layer = match self.default {
-            true => Some(quote! {
-                let default = #ident::default();
-            }),
+            true if self.variants().is_some() => {
+                panic!("#[layered(default)] isn't supported on enums, default their fields instead")
+            }
+            true => {
+                let (_, ty_generics, _) = self.generics.split_for_impl();
+                let turbofish = ty_generics.as_turbofish();
+                Some(quote! {
+                    let default = #ident #turbofish::default();
+                })
+            }
             false => None,
         };
 
-        let default_layer_field_list = fields
+        let env_layer_field_list = fields
+            .clone()
             .into_iter()
             .map(|f| {
                 let name = &f.ident;
                 let ty = &f.ty;
-                let subconfig = f.subconfig;
-                let default = &f.default;
-
-                let ty_id = match &ty {
-                    Type::Path(path) => match path.path.segments.first() {
-                        Some(seg) => &seg.ident,
-                        _ => panic!("Can't find ident"),
-                    },
-                    _ => panic!("Can't find ident"),
-                };
-                let layer_ident = format_ident!("{}Layer", ty_id);
 
-                if subconfig {
+                if self.flattened(f) {
                     quote! {
-                        #name: #layer_ident::default_layer(),
+                        #name: layeredconf::LayeredConfLayer::env_layer(vars)?,
                     }
-                } else {
-                    match default {
-                        Some(Override::Explicit(path)) => quote! {
-                            #name: Some(#path()),
-                        },
-                        Some(Override::Inherit) => quote! {
-                            #name: Some(std::default::Default::default()),
-                        },
-                        None => match self.default {
-                            true => quote! {
-                                #name: Some(default.#name),
-                            },
-                            false => quote! {
-                                #name: None,
-                            },
+                } else if let Some(var) = &f.env {
+                    let value = if self.is_option(ty) {
+                        quote! { layeredconf::from_env_var(#var, value)? }
+                    } else {
+                        quote! { Some(layeredconf::from_env_var(#var, value)?) }
+                    };
+                    quote! {
+                        #name: match vars.get(#var) {
+                            Some(value) => #value,
+                            None => None,
                         },
                     }
+                } else {
+                    quote! {
+                        #name: None,
+                    }
                 }
             })
             .collect::<Vec<_>>();
 
-        quote! {
-            impl layeredconf::LayeredConfSolid for #ident {
-                type Layer = #layer_ident;
-            }
-            impl layeredconf::LayeredConfLayer for #layer_ident {
-                type Config = #ident;
-
-                fn load_configs(&self) -> Vec<std::path::PathBuf> {
-                    let mut load_configs = vec![];
-
-                    #(#load_config_field_list)*
-
-                    load_configs
-                }
-
-                fn default_layer() -> Self {
-                    #default_layer
-
-                    Self {
-                        #(#default_layer_field_list)*
-                    }
-                }
-            }
-        }
-    }
-
-    fn to_layer_impl_tokens(&self) -> proc_macro2::TokenStream {
-        let layer_ident = self.layer_ident();
-
-        let fields = self.fields();
-
-        let empty_field_list = fields
+        let set_field_list = fields
+            .clone()
             .into_iter()
             .map(|f| {
-                let ident = &f.ident;
-                let subconfig = f.subconfig;
+                let name = &f.ident;
+                let field_key = self.key(f);
 
-                if subconfig {
+                if self.flattened(f) {
                     quote! {
-                        empty.push(self.#ident.empty());
+                        for field in layeredconf::LayeredConfLayer::set_fields(&self.#name) {
+                            set_fields.push(format!("{}.{}", #field_key, field));
+                        }
+                    }
+                } else if let Some((kind, _)) = self.subconfig(f) {
+                    let entries = entries(kind, quote! { self.#name.iter().flatten() });
+                    quote! {
+                        for (key, layer) in #entries {
+                            for field in layeredconf::LayeredConfLayer::set_fields(layer) {
+                                set_fields.push(format!("{}.{}.{}", #field_key, key, field));
+                            }
+                        }
                     }
                 } else {
                     quote! {
-                        empty.push(self.#ident.is_none());
+                        if self.#name.is_some() {
+                            set_fields.push(#field_key.to_string());
+                        }
                     }
                 }
             })
             .collect::<Vec<_>>();
 
-        quote! {
-            impl #layer_ident {
-                fn empty(&self) -> bool {
-                    let mut empty = vec![];
-
-                    #(#empty_field_list)*
+        let variants_field_list = fields
+            .clone()
+            .into_iter()
+            .filter_map(|f| {
+                let name = &f.ident;
+                let field_key = self.key(f);
 
-                    empty.iter().all(|v| *v)
+                if self.flattened(f) {
+                    Some(quote! {
+                        for (path, variant) in layeredconf::LayeredConfLayer::variants(&self.#name) {
+                            variants.push((format!("{}.{}", #field_key, path), variant));
+                        }
+                    })
+                } else {
+                    let (kind, _) = self.subconfig(f)?;
+                    let entries = entries(kind, quote! { self.#name.iter().flatten() });
+                    Some(quote! {
+                        for (key, layer) in #entries {
+                            for (path, variant) in layeredconf::LayeredConfLayer::variants(layer) {
+                                variants.push((format!("{}.{}.{}", #field_key, key, path), variant));
+                            }
+                        }
+                    })
                 }
-            }
-        }
-    }
-
-    fn to_layer_default_tokens(&self) -> proc_macro2::TokenStream {
-        let layer_ident = self.layer_ident();
-
-        let fields = self.fields();
+            })
+            .collect::<Vec<_>>();
 
-        let std_default_field_list = fields
+        let unset_field_list = fields
+            .clone()
             .into_iter()
-            .map(|f| {
+            .filter_map(|f| {
                 let name = &f.ident;
-                let ty = &f.ty;
-                let subconfig = f.subconfig;
-
-                let ty_id = match &ty {
-                    Type::Path(path) => match path.path.segments.first() {
-                        Some(seg) => &seg.ident,
-                        _ => panic!("Can't find ident"),
-                    },
-                    _ => panic!("Can't find ident"),
-                };
-                let layer_ident = format_ident!("{}Layer", ty_id);
+                let field_key = self.key(f);
 
-                if subconfig {
-                    quote! {
-                        #name: #layer_ident::default(),
-                    }
+                if self.flattened(f) {
+                    Some(quote! {
+                        for field in layeredconf::LayeredConfLayer::unset_fields(&self.#name) {
+                            unset_fields.push(format!("{}.{}", #field_key, field));
+                        }
+                    })
                 } else {
-                    quote! {
-                        #name: None,
-                    }
+                    let (kind, _) = self.subconfig(f)?;
+                    let entries = entries(kind, quote! { self.#name.iter().flatten() });
+                    Some(quote! {
+                        for (key, layer) in #entries {
+                            for field in layeredconf::LayeredConfLayer::unset_fields(layer) {
+                                unset_fields.push(format!("{}.{}.{}", #field_key, key, field));
+                            }
+                        }
+                    })
                 }
             })
             .collect::<Vec<_>>();
 
-        quote! {
-            impl std::default::Default for #layer_ident {
-                fn default() -> Self {
-                    Self {
-                        #(#std_default_field_list)*
+        let meta_field_list = fields
+            .clone()
+            .into_iter()
+            .map(|f| {
+                let name_str = f.ident.as_ref().map(|id| id.to_string());
+                let key = self.key(f);
+                let aliases = self.aliases(f);
+                let flatten = serde_flag(&f.attrs, "flatten");
+                let doc = option_tokens(doc_string(&f.attrs));
+
+                let kind = if let Some((kind, ty)) = self.subconfig(f) {
+                    let subconfig = quote! {
+                        layeredconf::meta::ValueKind::Subconfig(layeredconf::meta::subconfig::<#ty>)
+                    };
+                    match kind {
+                        SubconfigKind::Single | SubconfigKind::Optional => subconfig,
+                        SubconfigKind::Vec => quote! {
+                            layeredconf::meta::ValueKind::Array(Box::new(#subconfig))
+                        },
+                        SubconfigKind::Map => quote! {
+                            layeredconf::meta::ValueKind::Map(Box::new(#subconfig))
+                        },
                     }
-                }
-            }
-        }
-    }
+                } else {
+                    value_kind(&f.ty)
+                };
 
-    fn to_merge_tokens(&self) -> proc_macro2::TokenStream {
-        let layer_ident = self.layer_ident();
+                let optional = self.is_option(&f.ty);
+                // Fields of an enum's variants are only required when it's selected
+                let required = !f.subconfig
+                    && !self.is_option(&f.ty)
+                    && f.default.is_none()
+                    && !self.default
+                    && self.variants().is_none();
 
-        let fields = self.fields();
+                quote! {
+                    layeredconf::meta::FieldMeta {
+                        name: #name_str,
+                        key: #key,
+                        aliases: vec![#(#aliases),*],
+                        flatten: #flatten,
+                        doc: #doc,
+                        kind: #kind,
+                        required: #required,
+                        optional: #optional,
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
 
-        let field_list = fields
-            .into_iter()
+        let ident_str = ident.to_string();
+        let doc = option_tokens(doc_string(&self.attrs));
+
+        let empty_field_list = fields
+            .iter()
             .map(|f| {
                 let ident = &f.ident;
-                if f.subconfig {
+
+                if self.flattened(f) {
                     quote! {
-                        self.#ident.merge_from(&other.#ident);
+                        empty.push(layeredconf::LayeredConfLayer::empty(&self.#ident));
                     }
                 } else {
                     quote! {
-                        if self.#ident.is_none() {
-                            self.#ident = other.#ident.clone();
-                        }
+                        empty.push(self.#ident.is_none());
                     }
                 }
             })
             .collect::<Vec<_>>();
 
-        quote! {
-            impl layeredconf::LayeredConfMerge<#layer_ident> for #layer_ident {
-                fn merge_from(&mut self, other: &#layer_ident) {
-                    #(#field_list)*
-                }
-            }
-        }
-    }
+        let fill_optional_defaults_field_list = fields
+            .iter()
+            .filter_map(|f| {
+                let ident = &f.ident;
 
-    fn to_solidify_tokens(&self) -> proc_macro2::TokenStream {
-        let layer_ident = self.layer_ident();
+                let fill = quote! {
+                    layeredconf::LayeredConfLayer::fill_optional_defaults(&mut self.#ident);
+                };
+                Some(match self.subconfig(f)? {
+                    (SubconfigKind::Optional, _) => quote! {
+                        if let Some(layer) = layeredconf::optional_subconfig(&self.#ident) {
+                            self.#ident = layer;
+                        }
+                        #fill
+                    },
+                    (SubconfigKind::Single, _) => fill,
+                    // The defaults of a Config's Layer don't include its collections' entries,
+                    // so each gets its own, as it does when solidified
+                    (kind, _) => {
+                        let layers = match kind {
+                            SubconfigKind::Map => {
+                                quote! { self.#ident.iter_mut().flat_map(|layers| layers.values_mut()) }
+                            }
+                            _ => quote! { self.#ident.iter_mut().flatten() },
+                        };
+                        quote! {
+                            for layer in #layers {
+                                *layer = layeredconf::with_defaults(layer);
+                                layeredconf::LayeredConfLayer::fill_optional_defaults(layer);
+                            }
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
 
-        let fields = self.fields();
+        let template_defaults_field_list = fields
+            .iter()
+            .filter(|f| self.flattened(f))
+            .map(|f| {
+                let ident = &f.ident;
 
-        let field_list = fields
-            .clone()
+                quote! {
+                    layer.#ident = layeredconf::LayeredConfLayer::template_defaults();
+                }
+            })
+            .collect::<Vec<_>>();
+        let template_defaults = match template_defaults_field_list.is_empty() {
+            true => quote! { Self::default_layer() },
+            false => quote! {
+                let mut layer = Self::default_layer();
+                #(#template_defaults_field_list)*
+                layer
+            },
+        };
+
+        let default_layer_field_list = fields
             .into_iter()
             .map(|f| {
                 let name = &f.ident;
-                let name_str = name.as_ref().map(|id| id.to_string());
-                let ty = &f.ty;
+                let default = &f.default;
 
-                let option = self.is_option(ty);
+                if self.flattened(f) {
+                    match self.subconfig(f) {
+                        // Its defaults are filled in when solidified, if a Layer set any of
+                        // its fields, as they'd otherwise always enable it
+                        Some((SubconfigKind::Optional, _)) => quote! {
+                            #name: std::default::Default::default(),
+                        },
+                        _ => quote! {
+                            #name: layeredconf::LayeredConfLayer::default_layer(),
+                        },
+                    }
+                } else {
+                    match default {
+                        Some(Override::Explicit(path)) => quote! {
+                            #name: Some(#path()),
+                        },
+                        Some(Override::Inherit) => quote! {
+                            #name: Some(std::default::Default::default()),
+                        },
+                        None => match self.default {
+                            true => quote! {
+                                #name: Some(default.#name),
+                            },
+                            false => quote! {
+                                #name: None,
+                            },
+                        },
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
 
-                if option {
-                    if f.subconfig {
-                        panic!("layered(subconfig) shouldn't be wrapped in Option");
-                    } else {
+        let variant_none = self.variant_none();
+        let (variant_empty, variant_set, variant_meta) = match self.variants() {
+            Some(_) => {
+                let tag = self.tag();
+                (
+                    Some(quote! {
+                        empty.push(self._variant.is_none());
+                    }),
+                    Some(quote! {
+                        if self._variant.is_some() {
+                            set_fields.push(#tag.to_string());
+                        }
+                    }),
+                    Some(quote! {
+                        layeredconf::meta::FieldMeta {
+                            name: #tag,
+                            key: #tag,
+                            aliases: vec![],
+                            flatten: false,
+                            doc: None,
+                            kind: layeredconf::meta::ValueKind::String,
+                            required: true,
+                            optional: false,
+                        },
+                    }),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        let (variant_selected, variants_meta) = match self.variants() {
+            Some(variants) => {
+                let tag = self.tag();
+                let variant_ident = self.variant_ident();
+                let variant_name_list = variants
+                    .iter()
+                    .map(|v| {
+                        let ident = &v.ident;
+                        let name = ident.to_string();
+                        quote! {
+                            #variant_ident::#ident => #name,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let variant_meta_list = variants
+                    .iter()
+                    .map(|v| {
+                        let name = v.ident.to_string();
+                        let keys = v
+                            .fields
+                            .iter()
+                            .flat_map(|f| std::iter::once(self.key(f)).chain(self.aliases(f)));
                         quote! {
-                            let #name = self.#name.clone();
+                            layeredconf::meta::VariantMeta {
+                                name: #name,
+                                fields: vec![#(#keys),*],
+                            },
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    Some(quote! {
+                        if let Some(variant) = self._variant {
+                            let name = match variant {
+                                #(#variant_name_list)*
+                            };
+                            variants.push((#tag.to_string(), name));
                         }
+                    }),
+                    quote! {
+                        Some(layeredconf::meta::VariantsMeta {
+                            tag: #tag,
+                            variants: vec![
+                                #(#variant_meta_list)*
+                            ],
+                        })
+                    },
+                )
+            }
+            None => (None, quote! { None }),
+        };
+
+        let generics = self.owned_generics();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics layeredconf::LayeredConfSolid for #ident #ty_generics #where_clause {
+                type Layer = #layer_ident #ty_generics;
+            }
+            impl #impl_generics layeredconf::LayeredConfLayer for #layer_ident #ty_generics #where_clause {
+                type Config = #ident #ty_generics;
+
+                fn load_configs(&self) -> Vec<std::path::PathBuf> {
+                    let mut load_configs = vec![];
+
+                    #(#load_config_field_list)*
+
+                    load_configs
+                }
+
+                fn default_layer() -> Self {
+                    #default_layer
+
+                    Self {
+                        #(#default_layer_field_list)*
+                        #variant_none
+                        _unset: vec![],
+                        _present: false,
                     }
-                } else if f.subconfig {
+                }
+
+                fn template_defaults() -> Self {
+                    #template_defaults
+                }
+
+                fn present(&self) -> bool {
+                    self._present
+                }
+
+                fn fill_optional_defaults(&mut self) {
+                    #(#fill_optional_defaults_field_list)*
+                }
+
+                fn env_layer(
+                    vars: &std::collections::HashMap<String, String>,
+                ) -> layeredconf::Result<Self> {
+                    Ok(Self {
+                        #(#env_layer_field_list)*
+                        #variant_none
+                        _unset: vec![],
+                        _present: false,
+                    })
+                }
+
+                fn empty(&self) -> bool {
+                    let mut empty = vec![];
+
+                    #(#empty_field_list)*
+                    #variant_empty
+                    empty.push(self._unset.is_empty());
+
+                    empty.iter().all(|v| *v)
+                }
+
+                fn set_fields(&self) -> Vec<String> {
+                    let mut set_fields = vec![];
+
+                    #variant_set
+                    #(#set_field_list)*
+
+                    set_fields
+                }
+
+                fn variants(&self) -> Vec<(String, &'static str)> {
+                    let mut variants = vec![];
+
+                    #variant_selected
+                    #(#variants_field_list)*
+
+                    variants
+                }
+
+                fn unset_fields(&self) -> Vec<String> {
+                    let mut unset_fields = vec![];
+
+                    unset_fields.extend_from_slice(&self._unset);
+                    #(#unset_field_list)*
+
+                    unset_fields
+                }
+
+                fn meta() -> layeredconf::meta::StructMeta {
+                    layeredconf::meta::StructMeta {
+                        name: #ident_str,
+                        doc: #doc,
+                        fields: vec![
+                            #variant_meta
+                            #(#meta_field_list)*
+                        ],
+                        variants: #variants_meta,
+                    }
+                }
+            }
+        }
+    }
+
+    fn to_layer_default_tokens(&self) -> proc_macro2::TokenStream {
+        let layer_ident = self.layer_ident();
+
+        let fields = self.fields();
+
+        let std_default_field_list = fields
+            .into_iter()
+            .map(|f| {
+                let name = &f.ident;
+
+                if self.flattened(f) {
                     quote! {
-                        let #name = self.#name.solidify()?;
+                        #name: std::default::Default::default(),
                     }
                 } else {
                     quote! {
-                        let #name;
-                        if let Some(val) = &self.#name {
-                            #name = Some(val.clone());
-                        } else {
-                            #name = None;
-                            missing.push(#name_str.to_string());
-                        }
+                        #name: None,
                     }
                 }
             })
             .collect::<Vec<_>>();
 
-        let field_destructure = fields
+        let variant_none = self.variant_none();
+
+        let generics = self.layer_generics();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics std::default::Default for #layer_ident #ty_generics #where_clause {
+                fn default() -> Self {
+                    Self {
+                        #(#std_default_field_list)*
+                        #variant_none
+                        _unset: vec![],
+                        _present: false,
+                    }
+                }
+            }
+        }
+    }
+
+    fn to_merge_tokens(&self) -> proc_macro2::TokenStream {
+        let layer_ident = self.layer_ident();
+
+        let fields = self.fields();
+
+        let field_list = fields
+            .clone()
             .into_iter()
             .map(|f| {
-                let name = &f.ident;
-                let ty = &f.ty;
-                let option = self.is_option(ty);
-
-                if option || f.subconfig {
+                let ident = &f.ident;
+                // Unset paths are written with the keys of fields, as in config files
+                let key = self.key(f);
+                if self.flattened(f) {
+                    if f.merge != MergeStrategy::Replace {
+                        panic!("Subconfigs are always merged field by field");
+                    }
                     quote! {
-                        #name,
+                        layeredconf::LayeredConfMerge::push_unset(&mut self.#ident, &self._unset, #key);
+                        layeredconf::LayeredConfMerge::merge_from(&mut self.#ident, &other.#ident);
                     }
                 } else {
+                    let merge = match (self.subconfig(f), f.merge) {
+                        (Some((SubconfigKind::Map, _)), MergeStrategy::Replace) => quote! {
+                            layeredconf::merge::subconfig_map(&mut self.#ident, &other.#ident, &self._unset, #key);
+                        },
+                        (Some((SubconfigKind::Map, _)), _) => {
+                            panic!("Maps of subconfigs are always merged by key")
+                        }
+                        (Some((SubconfigKind::Vec, _)), MergeStrategy::Union) => {
+                            panic!("A Vec of subconfigs can't be merged with union")
+                        }
+                        (_, merge) => merge.to_tokens(ident),
+                    };
                     quote! {
-                        #name: #name.unwrap(),
+                        if !layeredconf::merge::is_unset(&self._unset, #key) {
+                            #merge
+                        }
                     }
                 }
             })
             .collect::<Vec<_>>();
 
+        let merge_unit_field_list = fields
+            .clone()
+            .into_iter()
+            .map(|f| {
+                let field_key = self.key(f);
+                let sub_unit = |depth: usize, ty: &Type| {
+                    let layer_ty = self.subconfig_layer_type(ty);
+                    quote! {
+                        Some(#field_key) => layeredconf::merge::sub_unit::<#layer_ty>(path, #depth),
+                    }
+                };
+                match self.subconfig(f) {
+                    Some((SubconfigKind::Single | SubconfigKind::Optional, ty)) => sub_unit(1, &ty),
+                    Some((SubconfigKind::Map, ty)) => sub_unit(2, &ty),
+                    _ => {
+                        let accumulates = f.merge != MergeStrategy::Replace;
+                        quote! {
+                            Some(#field_key) => Some((#field_key.to_string(), #accumulates)),
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let clear_unset_field_list = fields
+            .into_iter()
+            .filter_map(|f| {
+                let ident = &f.ident;
+                match self.subconfig(f)? {
+                    (SubconfigKind::Single | SubconfigKind::Optional, _) => Some(quote! {
+                        layeredconf::LayeredConfMerge::clear_unset(&mut self.#ident);
+                    }),
+                    (kind, _) => {
+                        let layers = match kind {
+                            SubconfigKind::Map => {
+                                quote! { self.#ident.iter_mut().flat_map(|layers| layers.values_mut()) }
+                            }
+                            _ => quote! { self.#ident.iter_mut().flatten() },
+                        };
+                        Some(quote! {
+                            for layer in #layers {
+                                layeredconf::LayeredConfMerge::clear_unset(layer);
+                            }
+                        })
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // A higher Layer that switches an enum's variant ignores Layers of other variants below
+        // it, and otherwise takes its variant from the highest Layer that selects one
+        let select_variant = self.variants().map(|_| {
+            let tag = self.tag();
+            quote! {
+                if let (Some(variant), Some(other_variant)) = (self._variant, other._variant) {
+                    if variant != other_variant {
+                        return;
+                    }
+                }
+                if self._variant.is_none() && !layeredconf::merge::is_unset(&self._unset, #tag) {
+                    self._variant = other._variant;
+                }
+            }
+        });
+
+        let generics = self.layer_generics();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics layeredconf::LayeredConfMerge<#layer_ident #ty_generics> for #layer_ident #ty_generics #where_clause {
+                fn merge_from(&mut self, other: &#layer_ident #ty_generics) {
+                    #select_variant
+                    #(#field_list)*
+                    // A section unset by a higher Layer isn't present because a lower one had it
+                    if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+                        self._present |= other._present;
+                    }
+                    layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+                }
+
+                fn push_unset(&mut self, unset: &[String], field: &str) {
+                    layeredconf::merge::push_unset(unset, field, &mut self._unset);
+                }
+
+                fn clear_unset(&mut self) {
+                    self._unset.clear();
+                    #(#clear_unset_field_list)*
+                }
+
+                fn merge_unit(path: &str) -> Option<(String, bool)> {
+                    match path.split('.').next() {
+                        #(#merge_unit_field_list)*
+                        _ => None,
+                    }
+                }
+            }
+        }
+    }
+
+    fn to_diff_tokens(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let layer_ident = self.layer_ident();
+
+        let fields = self.fields();
+
+        let field_list = |layer: bool| {
+            fields
+                .iter()
+                .map(|f| {
+                    let name = &f.ident;
+                    let field_key = self.key(f);
+                    if f.subconfig {
+                        quote! {
+                            layeredconf::LayeredConfDiff::diff_into(
+                                &self.#name,
+                                &other.#name,
+                                &layeredconf::field_path(prefix, #field_key),
+                                changes,
+                            );
+                        }
+                    } else if layer || self.is_option(&f.ty) {
+                        quote! {
+                            layeredconf::diff::option(prefix, #field_key, &self.#name, &other.#name, changes);
+                        }
+                    } else {
+                        quote! {
+                            layeredconf::diff::value(prefix, #field_key, &self.#name, &other.#name, changes);
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let solid_field_list = match self.variants() {
+            Some(variants) => vec![self.to_variant_diff_tokens(&variants)],
+            None => field_list(false),
+        };
+        let mut layer_field_list = field_list(true);
+        if self.variants().is_some() {
+            let tag = self.tag();
+            layer_field_list.insert(
+                0,
+                quote! {
+                    layeredconf::diff::option(prefix, #tag, &self._variant, &other._variant, changes);
+                },
+            );
+        }
+
+        let generics = self.layer_generics();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics layeredconf::LayeredConfDiff for #ident #ty_generics #where_clause {
+                fn diff_into(
+                    &self,
+                    other: &Self,
+                    prefix: &str,
+                    changes: &mut Vec<layeredconf::diff::Change>,
+                ) {
+                    #(#solid_field_list)*
+                }
+            }
+            impl #impl_generics layeredconf::LayeredConfDiff for #layer_ident #ty_generics #where_clause {
+                fn diff_into(
+                    &self,
+                    other: &Self,
+                    prefix: &str,
+                    changes: &mut Vec<layeredconf::diff::Change>,
+                ) {
+                    #(#layer_field_list)*
+                }
+            }
+        }
+    }
+
+    /// Compares two values of an enum field by field if they're the same variant, or as a
+    /// whole if not
+    fn to_variant_diff_tokens(&self, variants: &[&LayeredConfVariant]) -> proc_macro2::TokenStream {
         let ident = &self.ident;
 
+        let variant_list = variants
+            .iter()
+            .map(|v| {
+                let variant = &v.ident;
+                let names = v.fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+                let others = names
+                    .iter()
+                    .map(|name| format_ident!("other_{}", name.as_ref().expect("Should have an ident").unraw()))
+                    .collect::<Vec<_>>();
+                let diffs = v
+                    .fields
+                    .iter()
+                    .zip(&others)
+                    .map(|(f, other)| {
+                        let name = &f.ident;
+                        let field_key = self.key(f);
+                        if self.is_option(&f.ty) {
+                            quote! {
+                                layeredconf::diff::option(prefix, #field_key, #name, #other, changes);
+                            }
+                        } else {
+                            quote! {
+                                layeredconf::diff::value(prefix, #field_key, #name, #other, changes);
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                quote! {
+                    (
+                        #ident::#variant { #(#names,)* },
+                        #ident::#variant { #(#names: #others,)* },
+                    ) => {
+                        #(#diffs)*
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
         quote! {
-            impl layeredconf::LayeredConfSolidify<#ident> for #layer_ident {
-                fn solidify(&self) -> layeredconf::Result<#ident> {
-                    let mut missing = vec![];
+            #[allow(unreachable_patterns)]
+            match (self, other) {
+                #(#variant_list)*
+                (old, new) => layeredconf::diff::variant(prefix, old, new, changes),
+            };
+        }
+    }
 
-                    #(#field_list)*
+    fn to_solidify_tokens(&self) -> proc_macro2::TokenStream {
+        let layer_ident = self.layer_ident();
 
-                    if !missing.is_empty() {
-                        return Err(layeredconf::Error::SolidifyFailedMissing {
+        let fields = self.fields();
+
+        let missing_field = |f: &LayeredConfField| {
+            let name = &f.ident;
+            let field_key = self.key(f);
+            let ty = &f.ty;
+
+            if let Some((kind, ty)) = self.subconfig(f) {
+                match kind {
+                    SubconfigKind::Single => quote! {
+                        layeredconf::LayeredConfSolidify::<#ty>::missing_into(
+                            &self.#name,
+                            &layeredconf::field_path(prefix, #field_key),
                             missing,
+                        );
+                    },
+                    SubconfigKind::Optional => quote! {
+                        if let Some(layer) = layeredconf::optional_subconfig(&self.#name) {
+                            layeredconf::LayeredConfSolidify::<#ty>::missing_into(
+                                &layer,
+                                &layeredconf::field_path(prefix, #field_key),
+                                missing,
+                            );
+                        }
+                    },
+                    kind => {
+                        let entries = entries(kind, quote! { self.#name.iter().flatten() });
+                        quote! {
+                            for (key, layer) in #entries {
+                                layeredconf::entry_missing_into::<#ty, _>(
+                                    layer,
+                                    &layeredconf::field_path(
+                                        &layeredconf::field_path(prefix, #field_key),
+                                        &key.to_string(),
+                                    ),
+                                    missing,
+                                );
+                            }
+                        }
+                    }
+                }
+            } else if self.is_option(ty) {
+                quote! {}
+            } else {
+                let flag = match cli_flag(f) {
+                    Some(flag) => quote! { Some(#flag.to_string()) },
+                    None => quote! { None },
+                };
+                quote! {
+                    if self.#name.is_none() {
+                        missing.push(layeredconf::MissingField {
+                            path: layeredconf::field_path(prefix, #field_key),
+                            flag: #flag,
                         });
                     }
+                }
+            }
+        };
 
-                    Ok(#ident {
-                        #(#field_destructure)*
+        let solid_field = |f: &LayeredConfField| {
+            let name = &f.ident;
+            let ty = &f.ty;
+
+            if let Some((kind, ty)) = self.subconfig(f) {
+                match kind {
+                    SubconfigKind::Single => quote! {
+                        #name: layeredconf::LayeredConfSolidify::solidify_unchecked(&self.#name),
+                    },
+                    SubconfigKind::Optional => quote! {
+                        #name: layeredconf::optional_subconfig(&self.#name)
+                            .map(|layer| layeredconf::LayeredConfSolidify::solidify_unchecked(&layer)),
+                    },
+                    SubconfigKind::Vec => quote! {
+                        #name: self.#name.iter().flatten()
+                            .map(|layer| {
+                                layeredconf::LayeredConfSolidify::<#ty>::solidify_unchecked(
+                                    &layeredconf::with_defaults(layer),
+                                )
+                            })
+                            .collect(),
+                    },
+                    SubconfigKind::Map => quote! {
+                        #name: self.#name.iter().flatten()
+                            .map(|(key, layer)| {
+                                let solid = layeredconf::LayeredConfSolidify::<#ty>::solidify_unchecked(
+                                    &layeredconf::with_defaults(layer),
+                                );
+                                (key.clone(), solid)
+                            })
+                            .collect(),
+                    },
+                }
+            } else if self.is_option(ty) {
+                quote! {
+                    #name: self.#name.clone(),
+                }
+            } else {
+                quote! {
+                    #name: self.#name.clone().unwrap(),
+                }
+            }
+        };
+
+        // Checks a field of `solid`, given as a place expression for it
+        let validate_field = |f: &LayeredConfField, field: proc_macro2::TokenStream| {
+            let field_key = self.key(f);
+            let ty = &f.ty;
+
+            if let Some((kind, _)) = self.subconfig(f) {
+                let validate = |value, path| {
+                    quote! {
+                        layeredconf::validate::subconfig(#value, #path, failures);
+                    }
+                };
+                let path = quote! { &layeredconf::field_path(prefix, #field_key) };
+                return Some(match kind {
+                    SubconfigKind::Single => validate(quote! { &#field }, path),
+                    SubconfigKind::Optional => {
+                        let validate = validate(quote! { value }, path);
+                        quote! {
+                            if let Some(value) = &#field {
+                                #validate
+                            }
+                        }
+                    }
+                    kind => {
+                        let entries = entries(kind, quote! { #field.iter() });
+                        let validate = validate(
+                            quote! { value },
+                            quote! { &layeredconf::field_path(#path, &key.to_string()) },
+                        );
+                        quote! {
+                            for (key, value) in #entries {
+                                #validate
+                            }
+                        }
+                    }
+                });
+            }
+
+            let validations = f.validations();
+            if validations.is_empty() {
+                return None;
+            }
+            let checks = quote! {
+                #(layeredconf::validate::push(failures, prefix, #field_key, #validations);)*
+            };
+            Some(if self.is_option(ty) {
+                quote! {
+                    if let Some(value) = &#field {
+                        #checks
+                    }
+                }
+            } else {
+                quote! {
+                    {
+                        let value = &#field;
+                        #checks
+                    }
+                }
+            })
+        };
+
+        let validate_with = self.validate_with.as_ref().map(|validate_with| {
+            quote! {
+                layeredconf::validate::push_struct(
+                    failures,
+                    prefix,
+                    layeredconf::validate::custom(#validate_with(solid)),
+                );
+            }
+        });
+
+        let ident = &self.ident;
+
+        let (missing_into, solidify_unchecked, validate_into) = match self.variants() {
+            None => {
+                let missing_field_list = fields.iter().map(|f| missing_field(f));
+                let field_list = fields.iter().map(|f| solid_field(f));
+                let validate_field_list = fields
+                    .iter()
+                    .filter_map(|f| {
+                        let name = &f.ident;
+                        validate_field(f, quote! { solid.#name })
                     })
+                    .collect::<Vec<_>>();
+                (
+                    quote! { #(#missing_field_list)* },
+                    quote! {
+                        #ident {
+                            #(#field_list)*
+                        }
+                    },
+                    quote! { #(#validate_field_list)* },
+                )
+            }
+            // Only the fields of the variant that's finally selected are checked and used
+            Some(variants) => {
+                let tag = self.tag();
+                let ident_str = ident.to_string();
+                let variant_ident = self.variant_ident();
+
+                let missing_variant_list = variants.iter().map(|v| {
+                    let variant = &v.ident;
+                    let missing_field_list = v.fields.iter().map(missing_field);
+                    quote! {
+                        Some(#variant_ident::#variant) => {
+                            #(#missing_field_list)*
+                        }
+                    }
+                });
+                let solid_variant_list = variants.iter().map(|v| {
+                    let variant = &v.ident;
+                    let field_list = v.fields.iter().map(solid_field);
+                    quote! {
+                        Some(#variant_ident::#variant) => #ident::#variant {
+                            #(#field_list)*
+                        },
+                    }
+                });
+                let validate_variant_list = variants.iter().map(|v| {
+                    let variant = &v.ident;
+                    let (names, validate_field_list): (Vec<_>, Vec<_>) = v
+                        .fields
+                        .iter()
+                        .filter_map(|f| {
+                            let name = &f.ident;
+                            Some((name, validate_field(f, quote! { *#name })?))
+                        })
+                        .unzip();
+                    quote! {
+                        #ident::#variant { #(#names,)* .. } => {
+                            #(#validate_field_list)*
+                        }
+                    }
+                });
+
+                (
+                    quote! {
+                        match self._variant {
+                            #(#missing_variant_list)*
+                            None => missing.push(layeredconf::MissingField {
+                                path: layeredconf::field_path(prefix, #tag),
+                                flag: None,
+                            }),
+                        }
+                    },
+                    quote! {
+                        match self._variant {
+                            #(#solid_variant_list)*
+                            None => panic!("No variant of {} is selected", #ident_str),
+                        }
+                    },
+                    quote! {
+                        match solid {
+                            #(#validate_variant_list)*
+                        }
+                    },
+                )
+            }
+        };
+
+        let generics = self.layer_generics();
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl #impl_generics layeredconf::LayeredConfSolidify<#ident #ty_generics> for #layer_ident #ty_generics #where_clause {
+                fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+                    #missing_into
+                }
+
+                fn solidify_unchecked(&self) -> #ident #ty_generics {
+                    #solidify_unchecked
+                }
+
+                fn validate_into(
+                    solid: &#ident #ty_generics,
+                    prefix: &str,
+                    failures: &mut Vec<layeredconf::ValidationFailure>,
+                ) {
+                    #validate_into
+                    #validate_with
                 }
             }
         }
@@ -469,15 +1568,231 @@ impl LayeredConfStruct {
 
 impl ToTokens for LayeredConfStruct {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        if let Err(error) = self.check() {
+            tokens.extend(error.to_compile_error());
+            return;
+        }
         tokens.extend(self.to_layer_tokens());
+        tokens.extend(self.to_variant_tokens());
         tokens.extend(self.to_impl_layered_conf_tokens());
-        tokens.extend(self.to_layer_impl_tokens());
         tokens.extend(self.to_layer_default_tokens());
         tokens.extend(self.to_merge_tokens());
+        tokens.extend(self.to_diff_tokens());
         tokens.extend(self.to_solidify_tokens());
     }
 }
 
+/// Attributes to forward to the Layer, dropping `clap` attributes when it's disabled
+fn forward_attrs(attrs: &[syn::Attribute]) -> Vec<proc_macro2::TokenStream> {
+    attrs
+        .iter()
+        .filter(|a| cfg!(feature = "clap") || !a.path.is_ident("clap"))
+        .map(|a| a.into_token_stream())
+        .collect()
+}
+
+/// The value of `name` in `#[serde(name = ...)]` attributes
+fn serde_attr(attrs: &[syn::Attribute], name: &str) -> Option<syn::Lit> {
+    serde_attrs(attrs, name).into_iter().next()
+}
+
+/// Every value of `name` in `#[serde(name = ...)]` attributes, for those that can repeat
+fn serde_attrs(attrs: &[syn::Attribute], name: &str) -> Vec<syn::Lit> {
+    serde_metas(attrs)
+        .filter_map(|nested| match nested {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(value)) if value.path.is_ident(name) => {
+                Some(value.lit)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether there's a `#[serde(name)]` attribute
+fn serde_flag(attrs: &[syn::Attribute], name: &str) -> bool {
+    serde_metas(attrs).any(|nested| {
+        matches!(nested, syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident(name))
+    })
+}
+
+/// The items of `#[serde(...)]` attributes
+fn serde_metas(attrs: &[syn::Attribute]) -> impl Iterator<Item = syn::NestedMeta> + '_ {
+    attrs
+        .iter()
+        .filter(|a| a.path.is_ident("serde"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+}
+
+/// A snake_case field name, renamed by a `#[serde(rename_all = "...")]` rule
+fn rename_case(name: &str, rule: &str) -> String {
+    let pascal = || {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect::<String>()
+    };
+    match rule {
+        "lowercase" | "snake_case" => name.to_string(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => pascal,
+            }
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_uppercase().replace('_', "-"),
+        rule => panic!("Unknown #[serde(rename_all = {:?})] rule", rule),
+    }
+}
+
+/// A `#[clap(...)]` attribute, if clap is enabled
+fn clap_attr(tokens: proc_macro2::TokenStream) -> Option<proc_macro2::TokenStream> {
+    if cfg!(feature = "clap") {
+        Some(quote! { #[clap(#tokens)] })
+    } else {
+        None
+    }
+}
+
+/// The command line flag of a field, from its `#[clap(long)]` or `#[clap(short)]` attribute
+fn cli_flag(field: &LayeredConfField) -> Option<String> {
+    if !cfg!(feature = "clap") {
+        return None;
+    }
+    let name = field.ident.as_ref()?.unraw().to_string();
+
+    let mut long = None;
+    let mut short = None;
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("clap")) {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list,
+            _ => continue,
+        };
+        for nested in list.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("long") => {
+                    long = Some(name.replace('_', "-"));
+                }
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("short") => {
+                    short = name.chars().next();
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) if path.is_ident("long") => long = Some(lit.value()),
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Char(lit),
+                    ..
+                })) if path.is_ident("short") => short = Some(lit.value()),
+                _ => {}
+            }
+        }
+    }
+
+    long.map(|long| format!("--{}", long))
+        .or_else(|| short.map(|short| format!("-{}", short)))
+}
+
+/// The joined `#[doc = "..."]` attributes, with the space after each `///` removed
+fn doc_string(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines = attrs
+        .iter()
+        .filter(|a| a.path.is_ident("doc"))
+        .filter_map(|a| match a.parse_meta() {
+            Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                lit: syn::Lit::Str(lit),
+                ..
+            })) => Some(lit.value()),
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_string())
+        .collect::<Vec<_>>();
+
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() {
+        None
+    } else {
+        Some(doc)
+    }
+}
+
+fn option_tokens(value: Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+/// The `layeredconf::meta::ValueKind` of a field's type, going by the type's name
+fn value_kind(ty: &Type) -> proc_macro2::TokenStream {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        Type::Reference(reference) => return value_kind(&reference.elem),
+        _ => return quote! { layeredconf::meta::ValueKind::Any },
+    };
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return quote! { layeredconf::meta::ValueKind::Any },
+    };
+    let type_args = match &segment.arguments {
+        PathArguments::AngleBracketed(params) => params
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    match (segment.ident.to_string().as_str(), type_args.as_slice()) {
+        ("String" | "str" | "char" | "PathBuf" | "Path" | "OsString", []) => {
+            quote! { layeredconf::meta::ValueKind::String }
+        }
+        ("bool", []) => quote! { layeredconf::meta::ValueKind::Boolean },
+        (
+            "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128"
+            | "isize",
+            [],
+        ) => quote! { layeredconf::meta::ValueKind::Integer },
+        ("f32" | "f64", []) => quote! { layeredconf::meta::ValueKind::Number },
+        ("Option" | "Box", [inner]) => value_kind(inner),
+        ("Vec" | "VecDeque" | "HashSet" | "BTreeSet", [item]) => {
+            let item = value_kind(item);
+            quote! { layeredconf::meta::ValueKind::Array(Box::new(#item)) }
+        }
+        ("HashMap" | "BTreeMap", [_, value]) => {
+            let value = value_kind(value);
+            quote! { layeredconf::meta::ValueKind::Map(Box::new(#value)) }
+        }
+        _ => quote! { layeredconf::meta::ValueKind::Any },
+    }
+}
+
+#[derive(Debug, FromVariant)]
+#[darling(attributes(layered), forward_attrs(serde, doc))]
+struct LayeredConfVariant {
+    ident: Ident,
+    fields: ast::Fields<LayeredConfField>,
+    attrs: Vec<syn::Attribute>,
+}
+
 #[derive(Debug, FromField)]
 #[darling(attributes(layered), forward_attrs(clap, serde, doc))]
 struct LayeredConfField {
@@ -492,19 +1807,125 @@ struct LayeredConfField {
     load_config: bool,
     #[darling(default)]
     default: Option<Override<Path>>,
+    #[darling(default)]
+    env: Option<String>,
+    #[darling(default)]
+    merge: MergeStrategy,
+    #[darling(default)]
+    validate: Option<Path>,
+    #[darling(default)]
+    range: Option<RangeConstraint>,
+    #[darling(default)]
+    non_empty: bool,
+    #[darling(default)]
+    regex: Option<String>,
+    #[darling(default)]
+    path_exists: bool,
+}
+
+/// `#[layered(range(min = 1, max = 10))]`, with either bound optional
+#[derive(Debug, Default, FromMeta)]
+struct RangeConstraint {
+    #[darling(default)]
+    min: Option<syn::Lit>,
+    #[darling(default)]
+    max: Option<syn::Lit>,
+}
+
+impl RangeConstraint {
+    /// A bound as an expression, parsing string literals so negative bounds can be given
+    fn bound(bound: &Option<syn::Lit>) -> proc_macro2::TokenStream {
+        match bound {
+            Some(syn::Lit::Str(lit)) => {
+                let expr = lit
+                    .parse::<syn::Expr>()
+                    .expect("layered(range) bound should be an expression");
+                quote! { Some(#expr) }
+            }
+            Some(lit) => quote! { Some(#lit) },
+            None => quote! { None },
+        }
+    }
 }
 
-#[derive(Debug, FromMeta)]
-enum LayeredConfFieldSkip {
-    None,
-    Args,
+impl LayeredConfField {
+    /// Calls to the `layeredconf::validate` checks given in the field's attributes
+    fn validations(&self) -> Vec<proc_macro2::TokenStream> {
+        let mut validations = vec![];
+        if let Some(range) = &self.range {
+            let min = RangeConstraint::bound(&range.min);
+            let max = RangeConstraint::bound(&range.max);
+            validations.push(quote! { layeredconf::validate::range(value, #min, #max) });
+        }
+        if self.non_empty {
+            validations.push(quote! { layeredconf::validate::non_empty(value) });
+        }
+        if let Some(regex) = &self.regex {
+            validations.push(quote! { layeredconf::validate_regex!(value, #regex) });
+        }
+        if self.path_exists {
+            validations.push(quote! { layeredconf::validate::path_exists(value) });
+        }
+        if let Some(validate) = &self.validate {
+            validations.push(quote! { layeredconf::validate::custom(#validate(value)) });
+        }
+        validations
+    }
+}
+
+/// How a `subconfig` field holds its subconfig
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubconfigKind {
+    /// `sub: Sub`
+    Single,
+    /// `sub: Option<Sub>`, `None` unless a Layer sets one of its fields
+    Optional,
+    /// `subs: Vec<Sub>`, merged by the field's merge strategy
+    Vec,
+    /// `subs: HashMap<String, Sub>` or a `BTreeMap`, merged key by key
+    Map,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromMeta)]
+enum MergeStrategy {
+    #[default]
+    Replace,
+    Append,
+    Prepend,
+    Union,
+}
+
+impl MergeStrategy {
+    /// Merges the field `ident` of a lower Layer into this one
+    fn to_tokens(self, ident: &Option<Ident>) -> proc_macro2::TokenStream {
+        match self {
+            MergeStrategy::Replace => quote! {
+                if self.#ident.is_none() {
+                    self.#ident = other.#ident.clone();
+                }
+            },
+            MergeStrategy::Append => quote! {
+                layeredconf::merge::append(&mut self.#ident, &other.#ident);
+            },
+            MergeStrategy::Prepend => quote! {
+                layeredconf::merge::prepend(&mut self.#ident, &other.#ident);
+            },
+            MergeStrategy::Union => quote! {
+                layeredconf::merge::union(&mut self.#ident, &other.#ident);
+            },
+        }
+    }
 }
 
-impl std::default::Default for LayeredConfFieldSkip {
-    fn default() -> Self {
-        LayeredConfFieldSkip::None
+/// `(key, subconfig)` pairs of the entries of a `Vec` or map of subconfigs, from an
+/// iterator over them, keyed by index for `Vec`s
+fn entries(kind: SubconfigKind, iter: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match kind {
+        SubconfigKind::Vec => quote! { #iter.enumerate() },
+        _ => iter,
     }
 }
 
-#[cfg(test)]
+// The goldenfiles are the output with the clap feature
+#[cfg(all(test, feature = "clap"))]
 mod test;
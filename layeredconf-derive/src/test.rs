@@ -135,3 +135,237 @@ struct TestSubConfig {
     file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
         .unwrap();
 }
+
+#[test]
+fn test_env() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_env.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+struct Test {
+    #[layered(env = "PORT")]
+    port: u16,
+    #[layered(env = "APP_NAME")]
+    name: Option<String>,
+    #[layered(subconfig)]
+    subconfig: TestSubConfig,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_merge_strategies() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_merge_strategies.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+struct Test {
+    replaced: Vec<String>,
+    #[layered(merge = "append")]
+    appended: Vec<String>,
+    #[layered(merge = "prepend")]
+    prepended: Vec<String>,
+    #[layered(merge = "union")]
+    unioned: HashMap<String, String>,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_validate() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_validate.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+struct Test {
+    #[layered(range(min = 1, max = "u16::MAX"))]
+    port: u16,
+    #[layered(non_empty, regex = "^[a-z]+$")]
+    name: Option<String>,
+    #[layered(path_exists, validate = "check_path")]
+    path: PathBuf,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_validate_with() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_validate_with.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+#[layered(validate_with = "check_test")]
+struct Test {
+    min: u32,
+    max: u32,
+    #[layered(subconfig)]
+    subconfig: TestSubConfig,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_generics() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_generics.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+struct Test<B: Backend, T>
+where
+    T: Clone,
+{
+    limit: T,
+    #[layered(subconfig)]
+    backend: B,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_subconfig_path() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_subconfig_path.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+pub struct Test {
+    name: std::option::Option<String>,
+    #[layered(subconfig)]
+    db: crate::db::DbConfig,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_optional_subconfig() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_optional_subconfig.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+struct Test {
+    name: String,
+    #[layered(subconfig)]
+    tls: Option<TlsConfig>,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_subconfig_collections() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile("test_subconfig_collections.rs")
+        .unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+struct Test {
+    #[layered(subconfig)]
+    upstreams: std::collections::HashMap<String, Upstream>,
+    #[layered(subconfig, merge = "append")]
+    listeners: Vec<Listener>,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_enum() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_enum.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+#[layered(subconfig)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Storage {
+    S3 {
+        #[layered(non_empty)]
+        #[clap(long)]
+        bucket: String,
+        #[layered(default = "default_region")]
+        #[clap(long)]
+        region: String,
+    },
+    Local {
+        #[clap(long)]
+        path: Option<String>,
+    },
+    Memory,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn test_enum_subconfig_field() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("test_enum_subconfig_field.rs").unwrap();
+
+    let input = r#"
+#[derive(LayeredConf, serde::Deserialize)]
+#[layered(subconfig)]
+#[serde(tag = "type")]
+enum Storage {
+    S3 {
+        #[layered(subconfig)]
+        credentials: Credentials,
+    },
+    Memory,
+}
+"#;
+    let parsed = syn::parse_str(input).unwrap();
+    let conf_struct = LayeredConfStruct::from_derive_input(&parsed).unwrap();
+
+    file.write_all(rustfmt_ext(quote!(#conf_struct)).unwrap().as_bytes())
+        .unwrap();
+}
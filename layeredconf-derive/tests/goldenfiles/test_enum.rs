@@ -0,0 +1,318 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Args, Clone, Debug)]
+struct StorageLayer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[clap(long)]
+    bucket: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[clap(long)]
+    region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[clap(long)]
+    path: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    _variant: Option<StorageLayerVariant>,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+#[doc = "The variants of `Storage`, which its Layer selects by its tag"]
+#[derive(serde :: Deserialize, serde :: Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum StorageLayerVariant {
+    S3,
+    Local,
+    Memory,
+}
+impl layeredconf::LayeredConfSolid for Storage {
+    type Layer = StorageLayer;
+}
+impl layeredconf::LayeredConfLayer for StorageLayer {
+    type Config = Storage;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            bucket: None,
+            region: Some(default_region()),
+            path: None,
+            _variant: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        Self::default_layer()
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {}
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            bucket: None,
+            region: None,
+            path: None,
+            _variant: None,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.bucket.is_none());
+        empty.push(self.region.is_none());
+        empty.push(self.path.is_none());
+        empty.push(self._variant.is_none());
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self._variant.is_some() {
+            set_fields.push("type".to_string());
+        }
+        if self.bucket.is_some() {
+            set_fields.push("bucket".to_string());
+        }
+        if self.region.is_some() {
+            set_fields.push("region".to_string());
+        }
+        if self.path.is_some() {
+            set_fields.push("path".to_string());
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        if let Some(variant) = self._variant {
+            let name = match variant {
+                StorageLayerVariant::S3 => "S3",
+                StorageLayerVariant::Local => "Local",
+                StorageLayerVariant::Memory => "Memory",
+            };
+            variants.push(("type".to_string(), name));
+        }
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Storage",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "type",
+                    key: "type",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "bucket",
+                    key: "bucket",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: false,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "region",
+                    key: "region",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: false,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "path",
+                    key: "path",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: false,
+                    optional: true,
+                },
+            ],
+            variants: Some(layeredconf::meta::VariantsMeta {
+                tag: "type",
+                variants: vec![
+                    layeredconf::meta::VariantMeta {
+                        name: "S3",
+                        fields: vec!["bucket", "region"],
+                    },
+                    layeredconf::meta::VariantMeta {
+                        name: "Local",
+                        fields: vec!["path"],
+                    },
+                    layeredconf::meta::VariantMeta {
+                        name: "Memory",
+                        fields: vec![],
+                    },
+                ],
+            }),
+        }
+    }
+}
+impl std::default::Default for StorageLayer {
+    fn default() -> Self {
+        Self {
+            bucket: None,
+            region: None,
+            path: None,
+            _variant: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl layeredconf::LayeredConfMerge<StorageLayer> for StorageLayer {
+    fn merge_from(&mut self, other: &StorageLayer) {
+        if let (Some(variant), Some(other_variant)) = (self._variant, other._variant) {
+            if variant != other_variant {
+                return;
+            }
+        }
+        if self._variant.is_none() && !layeredconf::merge::is_unset(&self._unset, "type") {
+            self._variant = other._variant;
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "bucket") {
+            if self.bucket.is_none() {
+                self.bucket = other.bucket.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "region") {
+            if self.region.is_none() {
+                self.region = other.region.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "path") {
+            if self.path.is_none() {
+                self.path = other.path.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("bucket") => Some(("bucket".to_string(), false)),
+            Some("region") => Some(("region".to_string(), false)),
+            Some("path") => Some(("path".to_string(), false)),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for Storage {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        #[allow(unreachable_patterns)]
+        match (self, other) {
+            (
+                Storage::S3 { bucket, region },
+                Storage::S3 {
+                    bucket: other_bucket,
+                    region: other_region,
+                },
+            ) => {
+                layeredconf::diff::value(prefix, "bucket", bucket, other_bucket, changes);
+                layeredconf::diff::value(prefix, "region", region, other_region, changes);
+            }
+            (Storage::Local { path }, Storage::Local { path: other_path }) => {
+                layeredconf::diff::option(prefix, "path", path, other_path, changes);
+            }
+            (Storage::Memory {}, Storage::Memory {}) => {}
+            (old, new) => layeredconf::diff::variant(prefix, old, new, changes),
+        };
+    }
+}
+impl layeredconf::LayeredConfDiff for StorageLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "type", &self._variant, &other._variant, changes);
+        layeredconf::diff::option(prefix, "bucket", &self.bucket, &other.bucket, changes);
+        layeredconf::diff::option(prefix, "region", &self.region, &other.region, changes);
+        layeredconf::diff::option(prefix, "path", &self.path, &other.path, changes);
+    }
+}
+impl layeredconf::LayeredConfSolidify<Storage> for StorageLayer {
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        match self._variant {
+            Some(StorageLayerVariant::S3) => {
+                if self.bucket.is_none() {
+                    missing.push(layeredconf::MissingField {
+                        path: layeredconf::field_path(prefix, "bucket"),
+                        flag: Some("--bucket".to_string()),
+                    });
+                }
+                if self.region.is_none() {
+                    missing.push(layeredconf::MissingField {
+                        path: layeredconf::field_path(prefix, "region"),
+                        flag: Some("--region".to_string()),
+                    });
+                }
+            }
+            Some(StorageLayerVariant::Local) => {}
+            Some(StorageLayerVariant::Memory) => {}
+            None => missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "type"),
+                flag: None,
+            }),
+        }
+    }
+    fn solidify_unchecked(&self) -> Storage {
+        match self._variant {
+            Some(StorageLayerVariant::S3) => Storage::S3 {
+                bucket: self.bucket.clone().unwrap(),
+                region: self.region.clone().unwrap(),
+            },
+            Some(StorageLayerVariant::Local) => Storage::Local {
+                path: self.path.clone(),
+            },
+            Some(StorageLayerVariant::Memory) => Storage::Memory {},
+            None => panic!("No variant of {} is selected", "Storage"),
+        }
+    }
+    fn validate_into(
+        solid: &Storage,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        match solid {
+            Storage::S3 { bucket, .. } => {
+                let value = &*bucket;
+                layeredconf::validate::push(
+                    failures,
+                    prefix,
+                    "bucket",
+                    layeredconf::validate::non_empty(value),
+                );
+            }
+            Storage::Local { .. } => {}
+            Storage::Memory { .. } => {}
+        }
+    }
+}
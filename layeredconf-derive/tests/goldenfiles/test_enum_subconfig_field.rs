@@ -0,0 +1 @@
+compile_error! { "Fields of enum variants can't be subconfigs or load_config" }
@@ -0,0 +1,251 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Parser, Clone, Debug)]
+struct TestLayer<B: Backend, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+{
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    limit: Option<T>,
+    #[serde(default, skip_serializing_if = "layeredconf::absent_subconfig")]
+    #[clap(flatten)]
+    backend: B::Layer,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+impl<B: Backend, T> layeredconf::LayeredConfSolid for Test<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+    Test<B, T>: serde::de::DeserializeOwned,
+    TestLayer<B, T>: serde::de::DeserializeOwned,
+{
+    type Layer = TestLayer<B, T>;
+}
+impl<B: Backend, T> layeredconf::LayeredConfLayer for TestLayer<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+    Test<B, T>: serde::de::DeserializeOwned,
+    TestLayer<B, T>: serde::de::DeserializeOwned,
+{
+    type Config = Test<B, T>;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            limit: None,
+            backend: layeredconf::LayeredConfLayer::default_layer(),
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        let mut layer = Self::default_layer();
+        layer.backend = layeredconf::LayeredConfLayer::template_defaults();
+        layer
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {
+        layeredconf::LayeredConfLayer::fill_optional_defaults(&mut self.backend);
+    }
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            limit: None,
+            backend: layeredconf::LayeredConfLayer::env_layer(vars)?,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.limit.is_none());
+        empty.push(layeredconf::LayeredConfLayer::empty(&self.backend));
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.limit.is_some() {
+            set_fields.push("limit".to_string());
+        }
+        for field in layeredconf::LayeredConfLayer::set_fields(&self.backend) {
+            set_fields.push(format!("{}.{}", "backend", field));
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        for (path, variant) in layeredconf::LayeredConfLayer::variants(&self.backend) {
+            variants.push((format!("{}.{}", "backend", path), variant));
+        }
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        for field in layeredconf::LayeredConfLayer::unset_fields(&self.backend) {
+            unset_fields.push(format!("{}.{}", "backend", field));
+        }
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "limit",
+                    key: "limit",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Any,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "backend",
+                    key: "backend",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Subconfig(
+                        layeredconf::meta::subconfig::<B>,
+                    ),
+                    required: false,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
+}
+impl<B: Backend, T> std::default::Default for TestLayer<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+{
+    fn default() -> Self {
+        Self {
+            limit: None,
+            backend: std::default::Default::default(),
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl<B: Backend, T> layeredconf::LayeredConfMerge<TestLayer<B, T>> for TestLayer<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+{
+    fn merge_from(&mut self, other: &TestLayer<B, T>) {
+        if !layeredconf::merge::is_unset(&self._unset, "limit") {
+            if self.limit.is_none() {
+                self.limit = other.limit.clone();
+            }
+        }
+        layeredconf::LayeredConfMerge::push_unset(&mut self.backend, &self._unset, "backend");
+        layeredconf::LayeredConfMerge::merge_from(&mut self.backend, &other.backend);
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+        layeredconf::LayeredConfMerge::clear_unset(&mut self.backend);
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("limit") => Some(("limit".to_string(), false)),
+            Some("backend") => layeredconf::merge::sub_unit::<B::Layer>(path, 1usize),
+            _ => None,
+        }
+    }
+}
+impl<B: Backend, T> layeredconf::LayeredConfDiff for Test<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+{
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "limit", &self.limit, &other.limit, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.backend,
+            &other.backend,
+            &layeredconf::field_path(prefix, "backend"),
+            changes,
+        );
+    }
+}
+impl<B: Backend, T> layeredconf::LayeredConfDiff for TestLayer<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+{
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "limit", &self.limit, &other.limit, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.backend,
+            &other.backend,
+            &layeredconf::field_path(prefix, "backend"),
+            changes,
+        );
+    }
+}
+impl<B: Backend, T> layeredconf::LayeredConfSolidify<Test<B, T>> for TestLayer<B, T>
+where
+    T: Clone,
+    B: layeredconf::LayeredConfSolid + layeredconf::LayeredConfDiff,
+    B::Layer: layeredconf::LayeredConfSubLayer<B>,
+{
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.limit.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "limit"),
+                flag: None,
+            });
+        }
+        layeredconf::LayeredConfSolidify::<B>::missing_into(
+            &self.backend,
+            &layeredconf::field_path(prefix, "backend"),
+            missing,
+        );
+    }
+    fn solidify_unchecked(&self) -> Test<B, T> {
+        Test {
+            limit: self.limit.clone().unwrap(),
+            backend: layeredconf::LayeredConfSolidify::solidify_unchecked(&self.backend),
+        }
+    }
+    fn validate_into(
+        solid: &Test<B, T>,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        layeredconf::validate::subconfig(
+            &solid.backend,
+            &layeredconf::field_path(prefix, "backend"),
+            failures,
+        );
+    }
+}
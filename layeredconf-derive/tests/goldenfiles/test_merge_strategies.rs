@@ -0,0 +1,264 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Parser, Clone, Debug)]
+struct TestLayer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replaced: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    appended: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prepended: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unioned: Option<HashMap<String, String>>,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+impl layeredconf::LayeredConfSolid for Test {
+    type Layer = TestLayer;
+}
+impl layeredconf::LayeredConfLayer for TestLayer {
+    type Config = Test;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            replaced: None,
+            appended: None,
+            prepended: None,
+            unioned: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        Self::default_layer()
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {}
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            replaced: None,
+            appended: None,
+            prepended: None,
+            unioned: None,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.replaced.is_none());
+        empty.push(self.appended.is_none());
+        empty.push(self.prepended.is_none());
+        empty.push(self.unioned.is_none());
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.replaced.is_some() {
+            set_fields.push("replaced".to_string());
+        }
+        if self.appended.is_some() {
+            set_fields.push("appended".to_string());
+        }
+        if self.prepended.is_some() {
+            set_fields.push("prepended".to_string());
+        }
+        if self.unioned.is_some() {
+            set_fields.push("unioned".to_string());
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "replaced",
+                    key: "replaced",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Array(Box::new(
+                        layeredconf::meta::ValueKind::String,
+                    )),
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "appended",
+                    key: "appended",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Array(Box::new(
+                        layeredconf::meta::ValueKind::String,
+                    )),
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "prepended",
+                    key: "prepended",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Array(Box::new(
+                        layeredconf::meta::ValueKind::String,
+                    )),
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "unioned",
+                    key: "unioned",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Map(Box::new(
+                        layeredconf::meta::ValueKind::String,
+                    )),
+                    required: true,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
+}
+impl std::default::Default for TestLayer {
+    fn default() -> Self {
+        Self {
+            replaced: None,
+            appended: None,
+            prepended: None,
+            unioned: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
+    fn merge_from(&mut self, other: &TestLayer) {
+        if !layeredconf::merge::is_unset(&self._unset, "replaced") {
+            if self.replaced.is_none() {
+                self.replaced = other.replaced.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "appended") {
+            layeredconf::merge::append(&mut self.appended, &other.appended);
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "prepended") {
+            layeredconf::merge::prepend(&mut self.prepended, &other.prepended);
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "unioned") {
+            layeredconf::merge::union(&mut self.unioned, &other.unioned);
+        }
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("replaced") => Some(("replaced".to_string(), false)),
+            Some("appended") => Some(("appended".to_string(), true)),
+            Some("prepended") => Some(("prepended".to_string(), true)),
+            Some("unioned") => Some(("unioned".to_string(), true)),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "replaced", &self.replaced, &other.replaced, changes);
+        layeredconf::diff::value(prefix, "appended", &self.appended, &other.appended, changes);
+        layeredconf::diff::value(
+            prefix,
+            "prepended",
+            &self.prepended,
+            &other.prepended,
+            changes,
+        );
+        layeredconf::diff::value(prefix, "unioned", &self.unioned, &other.unioned, changes);
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "replaced", &self.replaced, &other.replaced, changes);
+        layeredconf::diff::option(prefix, "appended", &self.appended, &other.appended, changes);
+        layeredconf::diff::option(
+            prefix,
+            "prepended",
+            &self.prepended,
+            &other.prepended,
+            changes,
+        );
+        layeredconf::diff::option(prefix, "unioned", &self.unioned, &other.unioned, changes);
+    }
+}
+impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.replaced.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "replaced"),
+                flag: None,
+            });
+        }
+        if self.appended.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "appended"),
+                flag: None,
+            });
+        }
+        if self.prepended.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "prepended"),
+                flag: None,
+            });
+        }
+        if self.unioned.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "unioned"),
+                flag: None,
+            });
+        }
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            replaced: self.replaced.clone().unwrap(),
+            appended: self.appended.clone().unwrap(),
+            prepended: self.prepended.clone().unwrap(),
+            unioned: self.unioned.clone().unwrap(),
+        }
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+    }
+}
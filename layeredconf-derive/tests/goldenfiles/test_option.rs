@@ -6,6 +6,12 @@ struct TestLayer {
     integer: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     optional: Option<String>,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
 }
 impl layeredconf::LayeredConfSolid for Test {
     type Layer = TestLayer;
@@ -21,17 +27,95 @@ impl layeredconf::LayeredConfLayer for TestLayer {
             boolean: None,
             integer: None,
             optional: None,
+            _unset: vec![],
+            _present: false,
         }
     }
-}
-impl TestLayer {
+    fn template_defaults() -> Self {
+        Self::default_layer()
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {}
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            boolean: None,
+            integer: None,
+            optional: None,
+            _unset: vec![],
+            _present: false,
+        })
+    }
     fn empty(&self) -> bool {
         let mut empty = vec![];
         empty.push(self.boolean.is_none());
         empty.push(self.integer.is_none());
         empty.push(self.optional.is_none());
+        empty.push(self._unset.is_empty());
         empty.iter().all(|v| *v)
     }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.boolean.is_some() {
+            set_fields.push("boolean".to_string());
+        }
+        if self.integer.is_some() {
+            set_fields.push("integer".to_string());
+        }
+        if self.optional.is_some() {
+            set_fields.push("optional".to_string());
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "boolean",
+                    key: "boolean",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Boolean,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "integer",
+                    key: "integer",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Integer,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "optional",
+                    key: "optional",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: false,
+                    optional: true,
+                },
+            ],
+            variants: None,
+        }
+    }
 }
 impl std::default::Default for TestLayer {
     fn default() -> Self {
@@ -39,47 +123,88 @@ impl std::default::Default for TestLayer {
             boolean: None,
             integer: None,
             optional: None,
+            _unset: vec![],
+            _present: false,
         }
     }
 }
 impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
     fn merge_from(&mut self, other: &TestLayer) {
-        if self.boolean.is_none() {
-            self.boolean = other.boolean.clone();
+        if !layeredconf::merge::is_unset(&self._unset, "boolean") {
+            if self.boolean.is_none() {
+                self.boolean = other.boolean.clone();
+            }
         }
-        if self.integer.is_none() {
-            self.integer = other.integer.clone();
+        if !layeredconf::merge::is_unset(&self._unset, "integer") {
+            if self.integer.is_none() {
+                self.integer = other.integer.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "optional") {
+            if self.optional.is_none() {
+                self.optional = other.optional.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
         }
-        if self.optional.is_none() {
-            self.optional = other.optional.clone();
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("boolean") => Some(("boolean".to_string(), false)),
+            Some("integer") => Some(("integer".to_string(), false)),
+            Some("optional") => Some(("optional".to_string(), false)),
+            _ => None,
         }
     }
 }
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "boolean", &self.boolean, &other.boolean, changes);
+        layeredconf::diff::value(prefix, "integer", &self.integer, &other.integer, changes);
+        layeredconf::diff::option(prefix, "optional", &self.optional, &other.optional, changes);
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "boolean", &self.boolean, &other.boolean, changes);
+        layeredconf::diff::option(prefix, "integer", &self.integer, &other.integer, changes);
+        layeredconf::diff::option(prefix, "optional", &self.optional, &other.optional, changes);
+    }
+}
 impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
-    fn solidify(&self) -> layeredconf::Result<Test> {
-        let mut missing = vec![];
-        let boolean;
-        if let Some(val) = &self.boolean {
-            boolean = Some(val.clone());
-        } else {
-            boolean = None;
-            missing.push("boolean".to_string());
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.boolean.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "boolean"),
+                flag: None,
+            });
         }
-        let integer;
-        if let Some(val) = &self.integer {
-            integer = Some(val.clone());
-        } else {
-            integer = None;
-            missing.push("integer".to_string());
+        if self.integer.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "integer"),
+                flag: None,
+            });
         }
-        let optional = self.optional.clone();
-        if !missing.is_empty() {
-            return Err(layeredconf::Error::SolidifyFailedMissing { missing });
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            boolean: self.boolean.clone().unwrap(),
+            integer: self.integer.clone().unwrap(),
+            optional: self.optional.clone(),
         }
-        Ok(Test {
-            boolean: boolean.unwrap(),
-            integer: integer.unwrap(),
-            optional,
-        })
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
     }
 }
@@ -0,0 +1,311 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Parser, Clone, Debug)]
+struct TestLayer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    upstreams: Option<
+        std::collections::HashMap<String, <Upstream as layeredconf::LayeredConfSolid>::Layer>,
+    >,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    listeners: Option<Vec<<Listener as layeredconf::LayeredConfSolid>::Layer>>,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+impl layeredconf::LayeredConfSolid for Test {
+    type Layer = TestLayer;
+}
+impl layeredconf::LayeredConfLayer for TestLayer {
+    type Config = Test;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            upstreams: None,
+            listeners: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        Self::default_layer()
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {
+        for layer in self
+            .upstreams
+            .iter_mut()
+            .flat_map(|layers| layers.values_mut())
+        {
+            *layer = layeredconf::with_defaults(layer);
+            layeredconf::LayeredConfLayer::fill_optional_defaults(layer);
+        }
+        for layer in self.listeners.iter_mut().flatten() {
+            *layer = layeredconf::with_defaults(layer);
+            layeredconf::LayeredConfLayer::fill_optional_defaults(layer);
+        }
+    }
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            upstreams: None,
+            listeners: None,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.upstreams.is_none());
+        empty.push(self.listeners.is_none());
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        for (key, layer) in self.upstreams.iter().flatten() {
+            for field in layeredconf::LayeredConfLayer::set_fields(layer) {
+                set_fields.push(format!("{}.{}.{}", "upstreams", key, field));
+            }
+        }
+        for (key, layer) in self.listeners.iter().flatten().enumerate() {
+            for field in layeredconf::LayeredConfLayer::set_fields(layer) {
+                set_fields.push(format!("{}.{}.{}", "listeners", key, field));
+            }
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        for (key, layer) in self.upstreams.iter().flatten() {
+            for (path, variant) in layeredconf::LayeredConfLayer::variants(layer) {
+                variants.push((format!("{}.{}.{}", "upstreams", key, path), variant));
+            }
+        }
+        for (key, layer) in self.listeners.iter().flatten().enumerate() {
+            for (path, variant) in layeredconf::LayeredConfLayer::variants(layer) {
+                variants.push((format!("{}.{}.{}", "listeners", key, path), variant));
+            }
+        }
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        for (key, layer) in self.upstreams.iter().flatten() {
+            for field in layeredconf::LayeredConfLayer::unset_fields(layer) {
+                unset_fields.push(format!("{}.{}.{}", "upstreams", key, field));
+            }
+        }
+        for (key, layer) in self.listeners.iter().flatten().enumerate() {
+            for field in layeredconf::LayeredConfLayer::unset_fields(layer) {
+                unset_fields.push(format!("{}.{}.{}", "listeners", key, field));
+            }
+        }
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "upstreams",
+                    key: "upstreams",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Map(Box::new(
+                        layeredconf::meta::ValueKind::Subconfig(
+                            layeredconf::meta::subconfig::<Upstream>,
+                        ),
+                    )),
+                    required: false,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "listeners",
+                    key: "listeners",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Array(Box::new(
+                        layeredconf::meta::ValueKind::Subconfig(
+                            layeredconf::meta::subconfig::<Listener>,
+                        ),
+                    )),
+                    required: false,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
+}
+impl std::default::Default for TestLayer {
+    fn default() -> Self {
+        Self {
+            upstreams: None,
+            listeners: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
+    fn merge_from(&mut self, other: &TestLayer) {
+        if !layeredconf::merge::is_unset(&self._unset, "upstreams") {
+            layeredconf::merge::subconfig_map(
+                &mut self.upstreams,
+                &other.upstreams,
+                &self._unset,
+                "upstreams",
+            );
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "listeners") {
+            layeredconf::merge::append(&mut self.listeners, &other.listeners);
+        }
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+        for layer in self
+            .upstreams
+            .iter_mut()
+            .flat_map(|layers| layers.values_mut())
+        {
+            layeredconf::LayeredConfMerge::clear_unset(layer);
+        }
+        for layer in self.listeners.iter_mut().flatten() {
+            layeredconf::LayeredConfMerge::clear_unset(layer);
+        }
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("upstreams") => layeredconf::merge::sub_unit::<
+                <Upstream as layeredconf::LayeredConfSolid>::Layer,
+            >(path, 2usize),
+            Some("listeners") => Some(("listeners".to_string(), true)),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.upstreams,
+            &other.upstreams,
+            &layeredconf::field_path(prefix, "upstreams"),
+            changes,
+        );
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.listeners,
+            &other.listeners,
+            &layeredconf::field_path(prefix, "listeners"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.upstreams,
+            &other.upstreams,
+            &layeredconf::field_path(prefix, "upstreams"),
+            changes,
+        );
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.listeners,
+            &other.listeners,
+            &layeredconf::field_path(prefix, "listeners"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        for (key, layer) in self.upstreams.iter().flatten() {
+            layeredconf::entry_missing_into::<Upstream, _>(
+                layer,
+                &layeredconf::field_path(
+                    &layeredconf::field_path(prefix, "upstreams"),
+                    &key.to_string(),
+                ),
+                missing,
+            );
+        }
+        for (key, layer) in self.listeners.iter().flatten().enumerate() {
+            layeredconf::entry_missing_into::<Listener, _>(
+                layer,
+                &layeredconf::field_path(
+                    &layeredconf::field_path(prefix, "listeners"),
+                    &key.to_string(),
+                ),
+                missing,
+            );
+        }
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            upstreams: self
+                .upstreams
+                .iter()
+                .flatten()
+                .map(|(key, layer)| {
+                    let solid = layeredconf::LayeredConfSolidify::<Upstream>::solidify_unchecked(
+                        &layeredconf::with_defaults(layer),
+                    );
+                    (key.clone(), solid)
+                })
+                .collect(),
+            listeners: self
+                .listeners
+                .iter()
+                .flatten()
+                .map(|layer| {
+                    layeredconf::LayeredConfSolidify::<Listener>::solidify_unchecked(
+                        &layeredconf::with_defaults(layer),
+                    )
+                })
+                .collect(),
+        }
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        for (key, value) in solid.upstreams.iter() {
+            layeredconf::validate::subconfig(
+                value,
+                &layeredconf::field_path(
+                    &layeredconf::field_path(prefix, "upstreams"),
+                    &key.to_string(),
+                ),
+                failures,
+            );
+        }
+        for (key, value) in solid.listeners.iter().enumerate() {
+            layeredconf::validate::subconfig(
+                value,
+                &layeredconf::field_path(
+                    &layeredconf::field_path(prefix, "listeners"),
+                    &key.to_string(),
+                ),
+                failures,
+            );
+        }
+    }
+}
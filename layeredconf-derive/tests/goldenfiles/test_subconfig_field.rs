@@ -2,9 +2,15 @@
 struct TestLayer {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     name: Option<String>,
-    #[serde(default, skip_serializing_if = "TestSubConfigLayer :: empty")]
+    #[serde(default, skip_serializing_if = "layeredconf::absent_subconfig")]
     #[clap(flatten)]
-    subconfig: TestSubConfigLayer,
+    subconfig: <TestSubConfig as layeredconf::LayeredConfSolid>::Layer,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
 }
 impl layeredconf::LayeredConfSolid for Test {
     type Layer = TestLayer;
@@ -18,51 +24,186 @@ impl layeredconf::LayeredConfLayer for TestLayer {
     fn default_layer() -> Self {
         Self {
             name: None,
-            subconfig: TestSubConfigLayer::default_layer(),
+            subconfig: layeredconf::LayeredConfLayer::default_layer(),
+            _unset: vec![],
+            _present: false,
         }
     }
-}
-impl TestLayer {
+    fn template_defaults() -> Self {
+        let mut layer = Self::default_layer();
+        layer.subconfig = layeredconf::LayeredConfLayer::template_defaults();
+        layer
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {
+        layeredconf::LayeredConfLayer::fill_optional_defaults(&mut self.subconfig);
+    }
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            name: None,
+            subconfig: layeredconf::LayeredConfLayer::env_layer(vars)?,
+            _unset: vec![],
+            _present: false,
+        })
+    }
     fn empty(&self) -> bool {
         let mut empty = vec![];
         empty.push(self.name.is_none());
-        empty.push(self.subconfig.empty());
+        empty.push(layeredconf::LayeredConfLayer::empty(&self.subconfig));
+        empty.push(self._unset.is_empty());
         empty.iter().all(|v| *v)
     }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.name.is_some() {
+            set_fields.push("name".to_string());
+        }
+        for field in layeredconf::LayeredConfLayer::set_fields(&self.subconfig) {
+            set_fields.push(format!("{}.{}", "subconfig", field));
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        for (path, variant) in layeredconf::LayeredConfLayer::variants(&self.subconfig) {
+            variants.push((format!("{}.{}", "subconfig", path), variant));
+        }
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        for field in layeredconf::LayeredConfLayer::unset_fields(&self.subconfig) {
+            unset_fields.push(format!("{}.{}", "subconfig", field));
+        }
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "name",
+                    key: "name",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "subconfig",
+                    key: "subconfig",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Subconfig(
+                        layeredconf::meta::subconfig::<TestSubConfig>,
+                    ),
+                    required: false,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
 }
 impl std::default::Default for TestLayer {
     fn default() -> Self {
         Self {
             name: None,
-            subconfig: TestSubConfigLayer::default(),
+            subconfig: std::default::Default::default(),
+            _unset: vec![],
+            _present: false,
         }
     }
 }
 impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
     fn merge_from(&mut self, other: &TestLayer) {
-        if self.name.is_none() {
-            self.name = other.name.clone();
+        if !layeredconf::merge::is_unset(&self._unset, "name") {
+            if self.name.is_none() {
+                self.name = other.name.clone();
+            }
+        }
+        layeredconf::LayeredConfMerge::push_unset(&mut self.subconfig, &self._unset, "subconfig");
+        layeredconf::LayeredConfMerge::merge_from(&mut self.subconfig, &other.subconfig);
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+        layeredconf::LayeredConfMerge::clear_unset(&mut self.subconfig);
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("name") => Some(("name".to_string(), false)),
+            Some("subconfig") => layeredconf::merge::sub_unit::<
+                <TestSubConfig as layeredconf::LayeredConfSolid>::Layer,
+            >(path, 1usize),
+            _ => None,
         }
-        self.subconfig.merge_from(&other.subconfig);
+    }
+}
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "name", &self.name, &other.name, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.subconfig,
+            &other.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "name", &self.name, &other.name, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.subconfig,
+            &other.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            changes,
+        );
     }
 }
 impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
-    fn solidify(&self) -> layeredconf::Result<Test> {
-        let mut missing = vec![];
-        let name;
-        if let Some(val) = &self.name {
-            name = Some(val.clone());
-        } else {
-            name = None;
-            missing.push("name".to_string());
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.name.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "name"),
+                flag: None,
+            });
         }
-        let subconfig = self.subconfig.solidify()?;
-        if !missing.is_empty() {
-            return Err(layeredconf::Error::SolidifyFailedMissing { missing });
+        layeredconf::LayeredConfSolidify::<TestSubConfig>::missing_into(
+            &self.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            missing,
+        );
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            name: self.name.clone().unwrap(),
+            subconfig: layeredconf::LayeredConfSolidify::solidify_unchecked(&self.subconfig),
         }
-        Ok(Test {
-            name: name.unwrap(),
-            subconfig,
-        })
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        layeredconf::validate::subconfig(
+            &solid.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            failures,
+        );
     }
 }
@@ -0,0 +1,203 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Parser, Clone, Debug)]
+pub struct TestLayer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "layeredconf::absent_subconfig")]
+    #[clap(flatten)]
+    db: <crate::db::DbConfig as layeredconf::LayeredConfSolid>::Layer,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+impl layeredconf::LayeredConfSolid for Test {
+    type Layer = TestLayer;
+}
+impl layeredconf::LayeredConfLayer for TestLayer {
+    type Config = Test;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            name: None,
+            db: layeredconf::LayeredConfLayer::default_layer(),
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        let mut layer = Self::default_layer();
+        layer.db = layeredconf::LayeredConfLayer::template_defaults();
+        layer
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {
+        layeredconf::LayeredConfLayer::fill_optional_defaults(&mut self.db);
+    }
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            name: None,
+            db: layeredconf::LayeredConfLayer::env_layer(vars)?,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.name.is_none());
+        empty.push(layeredconf::LayeredConfLayer::empty(&self.db));
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.name.is_some() {
+            set_fields.push("name".to_string());
+        }
+        for field in layeredconf::LayeredConfLayer::set_fields(&self.db) {
+            set_fields.push(format!("{}.{}", "db", field));
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        for (path, variant) in layeredconf::LayeredConfLayer::variants(&self.db) {
+            variants.push((format!("{}.{}", "db", path), variant));
+        }
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        for field in layeredconf::LayeredConfLayer::unset_fields(&self.db) {
+            unset_fields.push(format!("{}.{}", "db", field));
+        }
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "name",
+                    key: "name",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: false,
+                    optional: true,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "db",
+                    key: "db",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Subconfig(
+                        layeredconf::meta::subconfig::<crate::db::DbConfig>,
+                    ),
+                    required: false,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
+}
+impl std::default::Default for TestLayer {
+    fn default() -> Self {
+        Self {
+            name: None,
+            db: std::default::Default::default(),
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
+    fn merge_from(&mut self, other: &TestLayer) {
+        if !layeredconf::merge::is_unset(&self._unset, "name") {
+            if self.name.is_none() {
+                self.name = other.name.clone();
+            }
+        }
+        layeredconf::LayeredConfMerge::push_unset(&mut self.db, &self._unset, "db");
+        layeredconf::LayeredConfMerge::merge_from(&mut self.db, &other.db);
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+        layeredconf::LayeredConfMerge::clear_unset(&mut self.db);
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("name") => Some(("name".to_string(), false)),
+            Some("db") => layeredconf::merge::sub_unit::<
+                <crate::db::DbConfig as layeredconf::LayeredConfSolid>::Layer,
+            >(path, 1usize),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "name", &self.name, &other.name, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.db,
+            &other.db,
+            &layeredconf::field_path(prefix, "db"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "name", &self.name, &other.name, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.db,
+            &other.db,
+            &layeredconf::field_path(prefix, "db"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        layeredconf::LayeredConfSolidify::<crate::db::DbConfig>::missing_into(
+            &self.db,
+            &layeredconf::field_path(prefix, "db"),
+            missing,
+        );
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            name: self.name.clone(),
+            db: layeredconf::LayeredConfSolidify::solidify_unchecked(&self.db),
+        }
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        layeredconf::validate::subconfig(
+            &solid.db,
+            &layeredconf::field_path(prefix, "db"),
+            failures,
+        );
+    }
+}
@@ -2,6 +2,12 @@
 struct TestSubConfigLayer {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     test: Option<String>,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
 }
 impl layeredconf::LayeredConfSolid for TestSubConfig {
     type Layer = TestSubConfigLayer;
@@ -13,43 +19,128 @@ impl layeredconf::LayeredConfLayer for TestSubConfigLayer {
         load_configs
     }
     fn default_layer() -> Self {
-        Self { test: None }
+        Self {
+            test: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        Self::default_layer()
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {}
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            test: None,
+            _unset: vec![],
+            _present: false,
+        })
     }
-}
-impl TestSubConfigLayer {
     fn empty(&self) -> bool {
         let mut empty = vec![];
         empty.push(self.test.is_none());
+        empty.push(self._unset.is_empty());
         empty.iter().all(|v| *v)
     }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.test.is_some() {
+            set_fields.push("test".to_string());
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "TestSubConfig",
+            doc: None,
+            fields: vec![layeredconf::meta::FieldMeta {
+                name: "test",
+                key: "test",
+                aliases: vec![],
+                flatten: false,
+                doc: None,
+                kind: layeredconf::meta::ValueKind::String,
+                required: true,
+                optional: false,
+            }],
+            variants: None,
+        }
+    }
 }
 impl std::default::Default for TestSubConfigLayer {
     fn default() -> Self {
-        Self { test: None }
+        Self {
+            test: None,
+            _unset: vec![],
+            _present: false,
+        }
     }
 }
 impl layeredconf::LayeredConfMerge<TestSubConfigLayer> for TestSubConfigLayer {
     fn merge_from(&mut self, other: &TestSubConfigLayer) {
-        if self.test.is_none() {
-            self.test = other.test.clone();
+        if !layeredconf::merge::is_unset(&self._unset, "test") {
+            if self.test.is_none() {
+                self.test = other.test.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
         }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("test") => Some(("test".to_string(), false)),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for TestSubConfig {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "test", &self.test, &other.test, changes);
+    }
+}
+impl layeredconf::LayeredConfDiff for TestSubConfigLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "test", &self.test, &other.test, changes);
     }
 }
 impl layeredconf::LayeredConfSolidify<TestSubConfig> for TestSubConfigLayer {
-    fn solidify(&self) -> layeredconf::Result<TestSubConfig> {
-        let mut missing = vec![];
-        let test;
-        if let Some(val) = &self.test {
-            test = Some(val.clone());
-        } else {
-            test = None;
-            missing.push("test".to_string());
-        }
-        if !missing.is_empty() {
-            return Err(layeredconf::Error::SolidifyFailedMissing { missing });
-        }
-        Ok(TestSubConfig {
-            test: test.unwrap(),
-        })
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.test.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "test"),
+                flag: None,
+            });
+        }
+    }
+    fn solidify_unchecked(&self) -> TestSubConfig {
+        TestSubConfig {
+            test: self.test.clone().unwrap(),
+        }
+    }
+    fn validate_into(
+        solid: &TestSubConfig,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
     }
 }
@@ -0,0 +1,248 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Parser, Clone, Debug)]
+struct TestLayer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+impl layeredconf::LayeredConfSolid for Test {
+    type Layer = TestLayer;
+}
+impl layeredconf::LayeredConfLayer for TestLayer {
+    type Config = Test;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            port: None,
+            name: None,
+            path: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        Self::default_layer()
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {}
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            port: None,
+            name: None,
+            path: None,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.port.is_none());
+        empty.push(self.name.is_none());
+        empty.push(self.path.is_none());
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.port.is_some() {
+            set_fields.push("port".to_string());
+        }
+        if self.name.is_some() {
+            set_fields.push("name".to_string());
+        }
+        if self.path.is_some() {
+            set_fields.push("path".to_string());
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "port",
+                    key: "port",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Integer,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "name",
+                    key: "name",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: false,
+                    optional: true,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "path",
+                    key: "path",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::String,
+                    required: true,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
+}
+impl std::default::Default for TestLayer {
+    fn default() -> Self {
+        Self {
+            port: None,
+            name: None,
+            path: None,
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
+    fn merge_from(&mut self, other: &TestLayer) {
+        if !layeredconf::merge::is_unset(&self._unset, "port") {
+            if self.port.is_none() {
+                self.port = other.port.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "name") {
+            if self.name.is_none() {
+                self.name = other.name.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "path") {
+            if self.path.is_none() {
+                self.path = other.path.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("port") => Some(("port".to_string(), false)),
+            Some("name") => Some(("name".to_string(), false)),
+            Some("path") => Some(("path".to_string(), false)),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "port", &self.port, &other.port, changes);
+        layeredconf::diff::option(prefix, "name", &self.name, &other.name, changes);
+        layeredconf::diff::value(prefix, "path", &self.path, &other.path, changes);
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "port", &self.port, &other.port, changes);
+        layeredconf::diff::option(prefix, "name", &self.name, &other.name, changes);
+        layeredconf::diff::option(prefix, "path", &self.path, &other.path, changes);
+    }
+}
+impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.port.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "port"),
+                flag: None,
+            });
+        }
+        if self.path.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "path"),
+                flag: None,
+            });
+        }
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            port: self.port.clone().unwrap(),
+            name: self.name.clone(),
+            path: self.path.clone().unwrap(),
+        }
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        {
+            let value = &solid.port;
+            layeredconf::validate::push(
+                failures,
+                prefix,
+                "port",
+                layeredconf::validate::range(value, Some(1), Some(u16::MAX)),
+            );
+        }
+        if let Some(value) = &solid.name {
+            layeredconf::validate::push(
+                failures,
+                prefix,
+                "name",
+                layeredconf::validate::non_empty(value),
+            );
+            layeredconf::validate::push(
+                failures,
+                prefix,
+                "name",
+                layeredconf::validate_regex!(value, "^[a-z]+$"),
+            );
+        }
+        {
+            let value = &solid.path;
+            layeredconf::validate::push(
+                failures,
+                prefix,
+                "path",
+                layeredconf::validate::path_exists(value),
+            );
+            layeredconf::validate::push(
+                failures,
+                prefix,
+                "path",
+                layeredconf::validate::custom(check_path(value)),
+            );
+        }
+    }
+}
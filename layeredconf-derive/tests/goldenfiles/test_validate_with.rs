@@ -0,0 +1,248 @@
+#[derive(serde :: Deserialize, serde :: Serialize, clap :: Parser, Clone, Debug)]
+struct TestLayer {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max: Option<u32>,
+    #[serde(default, skip_serializing_if = "layeredconf::absent_subconfig")]
+    #[clap(flatten)]
+    subconfig: <TestSubConfig as layeredconf::LayeredConfSolid>::Layer,
+    #[serde(rename = "_unset", default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    _unset: Vec<String>,
+    #[serde(skip, default = "layeredconf::merge::present")]
+    #[clap(skip)]
+    _present: bool,
+}
+impl layeredconf::LayeredConfSolid for Test {
+    type Layer = TestLayer;
+}
+impl layeredconf::LayeredConfLayer for TestLayer {
+    type Config = Test;
+    fn load_configs(&self) -> Vec<std::path::PathBuf> {
+        let mut load_configs = vec![];
+        load_configs
+    }
+    fn default_layer() -> Self {
+        Self {
+            min: None,
+            max: None,
+            subconfig: layeredconf::LayeredConfLayer::default_layer(),
+            _unset: vec![],
+            _present: false,
+        }
+    }
+    fn template_defaults() -> Self {
+        let mut layer = Self::default_layer();
+        layer.subconfig = layeredconf::LayeredConfLayer::template_defaults();
+        layer
+    }
+    fn present(&self) -> bool {
+        self._present
+    }
+    fn fill_optional_defaults(&mut self) {
+        layeredconf::LayeredConfLayer::fill_optional_defaults(&mut self.subconfig);
+    }
+    fn env_layer(vars: &std::collections::HashMap<String, String>) -> layeredconf::Result<Self> {
+        Ok(Self {
+            min: None,
+            max: None,
+            subconfig: layeredconf::LayeredConfLayer::env_layer(vars)?,
+            _unset: vec![],
+            _present: false,
+        })
+    }
+    fn empty(&self) -> bool {
+        let mut empty = vec![];
+        empty.push(self.min.is_none());
+        empty.push(self.max.is_none());
+        empty.push(layeredconf::LayeredConfLayer::empty(&self.subconfig));
+        empty.push(self._unset.is_empty());
+        empty.iter().all(|v| *v)
+    }
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = vec![];
+        if self.min.is_some() {
+            set_fields.push("min".to_string());
+        }
+        if self.max.is_some() {
+            set_fields.push("max".to_string());
+        }
+        for field in layeredconf::LayeredConfLayer::set_fields(&self.subconfig) {
+            set_fields.push(format!("{}.{}", "subconfig", field));
+        }
+        set_fields
+    }
+    fn variants(&self) -> Vec<(String, &'static str)> {
+        let mut variants = vec![];
+        for (path, variant) in layeredconf::LayeredConfLayer::variants(&self.subconfig) {
+            variants.push((format!("{}.{}", "subconfig", path), variant));
+        }
+        variants
+    }
+    fn unset_fields(&self) -> Vec<String> {
+        let mut unset_fields = vec![];
+        unset_fields.extend_from_slice(&self._unset);
+        for field in layeredconf::LayeredConfLayer::unset_fields(&self.subconfig) {
+            unset_fields.push(format!("{}.{}", "subconfig", field));
+        }
+        unset_fields
+    }
+    fn meta() -> layeredconf::meta::StructMeta {
+        layeredconf::meta::StructMeta {
+            name: "Test",
+            doc: None,
+            fields: vec![
+                layeredconf::meta::FieldMeta {
+                    name: "min",
+                    key: "min",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Integer,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "max",
+                    key: "max",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Integer,
+                    required: true,
+                    optional: false,
+                },
+                layeredconf::meta::FieldMeta {
+                    name: "subconfig",
+                    key: "subconfig",
+                    aliases: vec![],
+                    flatten: false,
+                    doc: None,
+                    kind: layeredconf::meta::ValueKind::Subconfig(
+                        layeredconf::meta::subconfig::<TestSubConfig>,
+                    ),
+                    required: false,
+                    optional: false,
+                },
+            ],
+            variants: None,
+        }
+    }
+}
+impl std::default::Default for TestLayer {
+    fn default() -> Self {
+        Self {
+            min: None,
+            max: None,
+            subconfig: std::default::Default::default(),
+            _unset: vec![],
+            _present: false,
+        }
+    }
+}
+impl layeredconf::LayeredConfMerge<TestLayer> for TestLayer {
+    fn merge_from(&mut self, other: &TestLayer) {
+        if !layeredconf::merge::is_unset(&self._unset, "min") {
+            if self.min.is_none() {
+                self.min = other.min.clone();
+            }
+        }
+        if !layeredconf::merge::is_unset(&self._unset, "max") {
+            if self.max.is_none() {
+                self.max = other.max.clone();
+            }
+        }
+        layeredconf::LayeredConfMerge::push_unset(&mut self.subconfig, &self._unset, "subconfig");
+        layeredconf::LayeredConfMerge::merge_from(&mut self.subconfig, &other.subconfig);
+        if !layeredconf::merge::is_unset(&self._unset, layeredconf::merge::UNSET_ALL) {
+            self._present |= other._present;
+        }
+        layeredconf::merge::extend_unset(&mut self._unset, &other._unset);
+    }
+    fn push_unset(&mut self, unset: &[String], field: &str) {
+        layeredconf::merge::push_unset(unset, field, &mut self._unset);
+    }
+    fn clear_unset(&mut self) {
+        self._unset.clear();
+        layeredconf::LayeredConfMerge::clear_unset(&mut self.subconfig);
+    }
+    fn merge_unit(path: &str) -> Option<(String, bool)> {
+        match path.split('.').next() {
+            Some("min") => Some(("min".to_string(), false)),
+            Some("max") => Some(("max".to_string(), false)),
+            Some("subconfig") => layeredconf::merge::sub_unit::<
+                <TestSubConfig as layeredconf::LayeredConfSolid>::Layer,
+            >(path, 1usize),
+            _ => None,
+        }
+    }
+}
+impl layeredconf::LayeredConfDiff for Test {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::value(prefix, "min", &self.min, &other.min, changes);
+        layeredconf::diff::value(prefix, "max", &self.max, &other.max, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.subconfig,
+            &other.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfDiff for TestLayer {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<layeredconf::diff::Change>) {
+        layeredconf::diff::option(prefix, "min", &self.min, &other.min, changes);
+        layeredconf::diff::option(prefix, "max", &self.max, &other.max, changes);
+        layeredconf::LayeredConfDiff::diff_into(
+            &self.subconfig,
+            &other.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            changes,
+        );
+    }
+}
+impl layeredconf::LayeredConfSolidify<Test> for TestLayer {
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<layeredconf::MissingField>) {
+        if self.min.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "min"),
+                flag: None,
+            });
+        }
+        if self.max.is_none() {
+            missing.push(layeredconf::MissingField {
+                path: layeredconf::field_path(prefix, "max"),
+                flag: None,
+            });
+        }
+        layeredconf::LayeredConfSolidify::<TestSubConfig>::missing_into(
+            &self.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            missing,
+        );
+    }
+    fn solidify_unchecked(&self) -> Test {
+        Test {
+            min: self.min.clone().unwrap(),
+            max: self.max.clone().unwrap(),
+            subconfig: layeredconf::LayeredConfSolidify::solidify_unchecked(&self.subconfig),
+        }
+    }
+    fn validate_into(
+        solid: &Test,
+        prefix: &str,
+        failures: &mut Vec<layeredconf::ValidationFailure>,
+    ) {
+        layeredconf::validate::subconfig(
+            &solid.subconfig,
+            &layeredconf::field_path(prefix, "subconfig"),
+            failures,
+        );
+        layeredconf::validate::push_struct(
+            failures,
+            prefix,
+            layeredconf::validate::custom(check_test(solid)),
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Renders Errors as diagnostics with [`miette`], with snippets of the offending config
+
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, MietteError, MietteSpanContents, SourceCode, SpanContents};
+
+use crate::{Error, Location, UnknownKey, ValidationFailure};
+
+impl SourceCode for Location {
+    fn read_span<'a>(
+        &'a self,
+        span: &miette::SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let text = self.text.as_deref().ok_or(MietteError::OutOfBounds)?;
+        let contents = text.read_span(span, context_lines_before, context_lines_after)?;
+        Ok(Box::new(MietteSpanContents::new_named(
+            self.origin_name(),
+            contents.data(),
+            *contents.span(),
+            contents.line(),
+            contents.column(),
+            contents.line_count(),
+        )))
+    }
+}
+
+/// Renders an [`Error`] with a snippet of the config, e.g. with [`miette::Report`]
+///
+/// Parse errors and validation failures underline the offending key or value in the file
+/// or string it was loaded from, labelled with what was wrong.
+///
+/// ```rust
+/// use layeredconf::{Builder, Format, LayeredConf, Source};
+///
+/// #[derive(LayeredConf, serde::Deserialize, Debug)]
+/// struct Config {
+///     #[layered(range(min = 1))]
+///     #[clap(long)]
+///     port: u16,
+/// }
+///
+/// let error = Builder::<Config>::new()
+///     .new_layer(Source::String("port: 0".to_string(), Format::Yaml))
+///     .solidify()
+///     .unwrap_err();
+///
+/// eprintln!("{:?}", miette::Report::new(error));
+/// ```
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let code = match self {
+            Error::At { source, .. } => return source.code(),
+            #[cfg(feature = "json")]
+            Error::JsonError { .. } => "layeredconf::json",
+            #[cfg(feature = "toml")]
+            Error::TomlError { .. } => "layeredconf::toml",
+            #[cfg(feature = "yaml")]
+            Error::YamlError { .. } => "layeredconf::yaml",
+            Error::FormatError { .. } => "layeredconf::format",
+            Error::EnvironmentError { .. } => "layeredconf::environment",
+            Error::SolidifyFailedMissing { .. } => "layeredconf::missing",
+            Error::ValidationFailed { .. } => "layeredconf::validation",
+            Error::UnknownKeys { .. } => "layeredconf::unknown_key",
+            _ => return None,
+        };
+        Some(Box::new(code))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let format = match self {
+            Error::At { source, .. } => return source.help(),
+            Error::SolidifyFailedMissing { missing } => {
+                return Some(Box::new(
+                    if missing.iter().any(|field| field.flag.is_some()) {
+                        "set them in a config file, the environment, or with their command line flags"
+                    } else {
+                        "set them in a config file or the environment"
+                    },
+                ));
+            }
+            #[cfg(feature = "json")]
+            Error::JsonError { .. } => "JSON",
+            #[cfg(feature = "toml")]
+            Error::TomlError { .. } => "TOML",
+            #[cfg(feature = "yaml")]
+            Error::YamlError { .. } => "YAML",
+            Error::FormatError { format, .. } => format,
+            Error::EnvironmentError { .. } => {
+                return Some(Box::new("check the variable's value has the field's type"));
+            }
+            _ => return None,
+        };
+        Some(Box::new(format!(
+            "check this is valid {}, with values of the fields' types",
+            format
+        )))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        let location = match self {
+            Error::At { location, .. } => location.as_ref(),
+            _ => return None,
+        };
+        location.text.as_ref()?;
+        Some(location)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (location, source) = match self {
+            Error::At { location, source } => (location, source),
+            _ => return None,
+        };
+        let span = location.span.clone()?;
+        let message = match source.as_ref() {
+            #[cfg(feature = "json")]
+            Error::JsonError { wrapped, .. } => wrapped.to_string(),
+            #[cfg(feature = "toml")]
+            Error::TomlError { wrapped, .. } => wrapped.to_string(),
+            #[cfg(feature = "yaml")]
+            Error::YamlError { wrapped, .. } => wrapped.to_string(),
+            Error::FormatError { wrapped, .. } => wrapped.to_string(),
+            _ => return None,
+        };
+        Some(Box::new(std::iter::once(LabeledSpan::at(
+            span,
+            strip_position(&message),
+        ))))
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        match self {
+            Error::ValidationFailed { failures } => Some(Box::new(
+                failures.iter().map(|failure| failure as &dyn Diagnostic),
+            )),
+            Error::UnknownKeys { unknown } => {
+                Some(Box::new(unknown.iter().map(|key| key as &dyn Diagnostic)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for ValidationFailure {}
+
+impl Diagnostic for ValidationFailure {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new("layeredconf::validation"))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        if self.path.is_empty() {
+            return None;
+        }
+        Some(Box::new(format!(
+            "change `{}` here, or override it with a later Layer",
+            self.path
+        )))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        let location = self.location.as_ref()?;
+        location.text.as_ref()?;
+        Some(location)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.location.as_ref()?.span.clone()?;
+        Some(Box::new(std::iter::once(LabeledSpan::at(
+            span,
+            self.message.clone(),
+        ))))
+    }
+}
+
+impl std::error::Error for UnknownKey {}
+
+impl Diagnostic for UnknownKey {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new("layeredconf::unknown_key"))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let suggestion = self.suggestion.as_ref()?;
+        Some(Box::new(format!("did you mean `{}`?", suggestion)))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        let location = self.location.as_ref()?;
+        location.text.as_ref()?;
+        Some(location)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = self.location.as_ref()?.span.clone()?;
+        Some(Box::new(std::iter::once(LabeledSpan::at(
+            span,
+            "not a field of the config",
+        ))))
+    }
+}
+
+/// Strips the ` at line 2 column 8` suffix the parsers add, as the snippet shows it
+fn strip_position(message: &str) -> &str {
+    match message.rfind(" at line ") {
+        Some(index)
+            if message[index + " at line ".len()..]
+                .split(" column ")
+                .all(|number| number.parse::<usize>().is_ok()) =>
+        {
+            &message[..index]
+        }
+        _ => message,
+    }
+}
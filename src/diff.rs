@@ -0,0 +1,167 @@
+//! Differences between two Configs, or two Layers, by field path
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Debug, Display},
+    hash::{BuildHasher, Hash},
+};
+
+/// A field that differs between two Configs or Layers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Dotted path of the field by serde's keys, e.g. `db.pool-size` with `rename_all = "kebab-case"`
+    pub path: String,
+    /// Debug representation of the old value, `None` if a Layer didn't set it
+    pub old: Option<String>,
+    /// Debug representation of the new value, `None` if a Layer doesn't set it
+    pub new: Option<String>,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let old = self.old.as_deref().unwrap_or("(unset)");
+        let new = self.new.as_deref().unwrap_or("(unset)");
+        write!(f, "{}: {} -> {}", self.path, old, new)
+    }
+}
+
+/// Compares two Configs, or two Layers, field by field
+///
+/// Implemented by the derive macro for Configs and their Layers. Values are compared by
+/// their `Debug` representation, and subconfigs are compared field by field.
+///
+/// ```rust
+/// use layeredconf::{LayeredConf, LayeredConfDiff};
+///
+/// #[derive(LayeredConf, serde::Deserialize)]
+/// struct Config {
+///     #[clap(long)]
+///     name: String,
+///     #[layered(subconfig)]
+///     subconfig: SubConfig,
+/// }
+///
+/// #[derive(LayeredConf, serde::Deserialize)]
+/// #[layered(subconfig)]
+/// struct SubConfig {
+///     #[clap(long)]
+///     flibble: u64,
+/// }
+///
+/// let old = Config { name: "same".to_string(), subconfig: SubConfig { flibble: 10 } };
+/// let new = Config { name: "same".to_string(), subconfig: SubConfig { flibble: 12 } };
+///
+/// let changes = old.diff(&new);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(changes[0].to_string(), "subconfig.flibble: 10 -> 12");
+/// ```
+pub trait LayeredConfDiff {
+    /// Returns the fields that differ from `self` to `other`, in declaration order
+    fn diff(&self, other: &Self) -> Vec<Change> {
+        let mut changes = vec![];
+        self.diff_into(other, "", &mut changes);
+        changes
+    }
+
+    #[doc(hidden)]
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<Change>);
+}
+
+/// Optional subconfigs are compared field by field while both are set
+impl<T: LayeredConfDiff + Debug> LayeredConfDiff for Option<T> {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<Change>) {
+        match (self, other) {
+            (Some(old), Some(new)) => old.diff_into(new, prefix, changes),
+            (old, new) => push(prefix.to_string(), old.as_ref(), new.as_ref(), changes),
+        }
+    }
+}
+
+/// Lists of subconfigs are compared entry by entry, by index
+impl<T: LayeredConfDiff + Debug> LayeredConfDiff for Vec<T> {
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<Change>) {
+        for index in 0..self.len().max(other.len()) {
+            entry(prefix, index, self.get(index), other.get(index), changes);
+        }
+    }
+}
+
+/// Maps of subconfigs are compared entry by entry, by key
+impl<K, V, S> LayeredConfDiff for HashMap<K, V, S>
+where
+    K: Display + Eq + Hash + Ord,
+    V: LayeredConfDiff + Debug,
+    S: BuildHasher,
+{
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<Change>) {
+        let keys = self.keys().chain(other.keys()).collect::<BTreeSet<_>>();
+        for key in keys {
+            entry(prefix, key, self.get(key), other.get(key), changes);
+        }
+    }
+}
+
+/// Maps of subconfigs are compared entry by entry, by key
+impl<K, V> LayeredConfDiff for BTreeMap<K, V>
+where
+    K: Display + Ord,
+    V: LayeredConfDiff + Debug,
+{
+    fn diff_into(&self, other: &Self, prefix: &str, changes: &mut Vec<Change>) {
+        let keys = self.keys().chain(other.keys()).collect::<BTreeSet<_>>();
+        for key in keys {
+            entry(prefix, key, self.get(key), other.get(key), changes);
+        }
+    }
+}
+
+/// Compares the entries at `key` of two collections, field by field if both have one
+fn entry<T: LayeredConfDiff + Debug>(
+    prefix: &str,
+    key: impl Display,
+    old: Option<&T>,
+    new: Option<&T>,
+    changes: &mut Vec<Change>,
+) {
+    let path = crate::field_path(prefix, &key.to_string());
+    match (old, new) {
+        (Some(old), Some(new)) => old.diff_into(new, &path, changes),
+        (old, new) => push(path, old, new, changes),
+    }
+}
+
+#[doc(hidden)]
+pub fn value<T: Debug>(prefix: &str, name: &str, old: &T, new: &T, changes: &mut Vec<Change>) {
+    option(prefix, name, &Some(old), &Some(new), changes);
+}
+
+#[doc(hidden)]
+pub fn option<T: Debug>(
+    prefix: &str,
+    name: &str,
+    old: &Option<T>,
+    new: &Option<T>,
+    changes: &mut Vec<Change>,
+) {
+    push(
+        crate::field_path(prefix, name),
+        old.as_ref(),
+        new.as_ref(),
+        changes,
+    );
+}
+
+/// Compares two values of an enum with different variants as a whole
+#[doc(hidden)]
+pub fn variant<T: Debug>(prefix: &str, old: &T, new: &T, changes: &mut Vec<Change>) {
+    push(prefix.to_string(), Some(old), Some(new), changes);
+}
+
+/// Adds a Change at `path` if the values' Debug representations differ
+fn push<T: Debug>(path: String, old: Option<&T>, new: Option<&T>, changes: &mut Vec<Change>) {
+    let old = old.map(|old| format!("{:?}", old));
+    let new = new.map(|new| format!("{:?}", new));
+    if old != new {
+        changes.push(Change { path, old, new });
+    }
+}
@@ -0,0 +1,333 @@
+//! Deserializes a Layer from environment variables
+//!
+//! Variable names have their prefix stripped, are lowercased, and are split on
+//! `__` to address fields in subconfigs, so with a prefix of `MYAPP_` the
+//! variable `MYAPP_DB__URI` sets the `uri` field of the `db` subconfig.
+//!
+//! Keys are matched to serde's names for fields ignoring case, `-` and `_`, so
+//! `MYAPP_DB__POOL_SIZE` also sets a field renamed to `pool-size` or `poolSize`.
+//!
+//! Lists are either comma separated, `MYAPP_LIST=a,b`, or indexed,
+//! `MYAPP_LIST__0=a` and `MYAPP_LIST__1=b`, with entries ordered by index.
+//!
+//! Setting a field and fields within it, like `MYAPP_DB` and `MYAPP_DB__URI`, is an error.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::de::{
+    self,
+    value::{Error, MapDeserializer, StringDeserializer},
+    DeserializeOwned, IntoDeserializer, Visitor,
+};
+
+const SEPARATOR: &str = "__";
+
+/// Deserializes `T` from `vars`, ignoring any that don't start with `prefix`
+pub(crate) fn from_vars<T, I>(prefix: Option<&str>, vars: I) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = (String, String)>,
+{
+    let entries = vars.into_iter().filter_map(|(var, value)| {
+        let key = match prefix {
+            Some(prefix) => var.strip_prefix(prefix)?,
+            None => var.as_str(),
+        };
+        let path = key
+            .to_lowercase()
+            .split(SEPARATOR)
+            .map(str::to_string)
+            .collect();
+        Some((path, var, value))
+    });
+
+    T::deserialize(tree(entries)?)
+}
+
+/// Builds a deserializer from string values at field `path`s, named `var` in errors
+///
+/// Fails if a value is set both at a path and within it, e.g. by `MYAPP_DB` and
+/// `MYAPP_DB__URI`, or twice at a path, as which would win is down to the order of `entries`.
+pub(crate) fn tree<I>(entries: I) -> Result<Node, Error>
+where
+    I: IntoIterator<Item = (Vec<String>, String, String)>,
+{
+    let mut root = BTreeMap::new();
+
+    for (path, var, value) in entries {
+        if path.iter().any(|segment| segment.is_empty()) {
+            continue;
+        }
+        Node::insert(&mut root, &path, Node::Value { var, value })?;
+    }
+
+    Ok(Node::Map(root))
+}
+
+/// Deserializes `T` from the value of a single variable
+pub(crate) fn from_var<T>(var: &str, value: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Node::Value {
+        var: var.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// Returns the process env, skipping variables that aren't valid unicode
+pub(crate) fn vars() -> HashMap<String, String> {
+    std::env::vars_os()
+        .filter_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+/// The serde name of the field `key` addresses, or `key` if it addresses none
+fn field_key(fields: &[&str], key: String) -> String {
+    fn normalize(name: &str) -> String {
+        name.chars()
+            .filter(|c| *c != '_' && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+
+    if fields.contains(&key.as_str()) {
+        return key;
+    }
+    let normalized = normalize(&key);
+    match fields.iter().find(|field| normalize(field) == normalized) {
+        Some(field) => field.to_string(),
+        None => key,
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Node {
+    Value { var: String, value: String },
+    Map(BTreeMap<String, Node>),
+}
+
+impl Node {
+    fn insert(map: &mut BTreeMap<String, Node>, path: &[String], node: Node) -> Result<(), Error> {
+        match path {
+            [] => Ok(()),
+            [key] => match map.get(key) {
+                Some(existing) => Err(Node::conflict(existing, &node)),
+                None => {
+                    map.insert(key.to_string(), node);
+                    Ok(())
+                }
+            },
+            [key, rest @ ..] => {
+                let entry = map
+                    .entry(key.to_string())
+                    .or_insert_with(|| Node::Map(BTreeMap::new()));
+                match entry {
+                    Node::Map(map) => Node::insert(map, rest, node),
+                    Node::Value { .. } => Err(Node::conflict(entry, &node)),
+                }
+            }
+        }
+    }
+
+    /// The error for two variables setting the same value, or a value and values within it
+    fn conflict(existing: &Node, node: &Node) -> Error {
+        de::Error::custom(format!(
+            "{} and {} conflict, setting a field and also it or fields within it",
+            existing.var(),
+            node.var(),
+        ))
+    }
+
+    /// The name of a variable that sets the value, or one of the values within it
+    fn var(&self) -> &str {
+        match self {
+            Node::Value { var, .. } => var,
+            Node::Map(map) => map.values().next().map_or("", Node::var),
+        }
+    }
+
+    fn parse<T>(self) -> Result<T, Error>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match self {
+            Node::Value { var, value } => value
+                .trim()
+                .parse()
+                .map_err(|error| de::Error::custom(format!("{}: {}", var, error))),
+            Node::Map(_) => Err(de::Error::invalid_type(
+                de::Unexpected::Map,
+                &"a single value",
+            )),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Node {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Error>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Node {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Value { value, .. } => visitor.visit_string(value),
+            Node::Map(map) => visitor.visit_map(MapDeserializer::new(map.into_iter())),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Value { var, value } => {
+                let items = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| Node::Value {
+                        var: var.clone(),
+                        value: item.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                visitor.visit_seq(de::value::SeqDeserializer::new(items.into_iter()))
+            }
+            Node::Map(map) => {
+                let mut items = map
+                    .into_iter()
+                    .map(|(key, node)| match key.parse::<usize>() {
+                        Ok(index) => Ok((index, node)),
+                        Err(_) => Err(de::Error::custom(format!(
+                            "invalid list index {:?}, expected a number",
+                            key
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                items.sort_by_key(|(index, _)| *index);
+                visitor.visit_seq(de::value::SeqDeserializer::new(
+                    items.into_iter().map(|(_, node)| node),
+                ))
+            }
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Map(map) => {
+                let map = map
+                    .into_iter()
+                    .map(|(key, node)| (field_key(fields, key), node));
+                visitor.visit_map(MapDeserializer::new(map))
+            }
+            node => node.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Node::Value { value, .. } => {
+                let deserializer: StringDeserializer<Error> = value.into_deserializer();
+                visitor.visit_enum(deserializer)
+            }
+            Node::Map(_) => Err(de::Error::invalid_type(de::Unexpected::Map, &visitor)),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct map identifier ignored_any
+    }
+}
@@ -0,0 +1,222 @@
+//! Pluggable config file formats
+//!
+//! JSON, TOML and YAML are built in. Further formats implement [`FormatBackend`] and are
+//! registered with [`Builder::register_format`](crate::Builder::register_format), after
+//! which they're used for files with one of their extensions, or when selected with
+//! [`Format::Custom`](crate::Format::Custom).
+//!
+//! These extra formats are available behind cargo features, and are registered with every
+//! Builder when enabled:
+//! - `ron`: [`Ron`]
+//! - `json5`: [`Json5`]
+//! - `ini`: [`Ini`]
+
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Error, Result};
+
+/// Boxed error returned by a [`FormatBackend`]
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Callback given a format's deserializer by [`FormatBackend::deserialize`]
+pub type Visit<'a> = dyn FnMut(&mut dyn erased_serde::Deserializer) -> std::result::Result<(), erased_serde::Error>
+    + 'a;
+
+/// A config file format
+///
+/// ```rust
+/// use layeredconf::formats::{BoxError, FormatBackend, Visit};
+///
+/// #[derive(Debug)]
+/// struct Json;
+///
+/// impl FormatBackend for Json {
+///     fn name(&self) -> &'static str {
+///         "my_json"
+///     }
+///
+///     fn extensions(&self) -> &[&'static str] {
+///         &["my_json"]
+///     }
+///
+///     fn deserialize(&self, input: &str, visit: &mut Visit) -> Result<(), BoxError> {
+///         let mut deserializer = serde_json::Deserializer::from_str(input);
+///         visit(&mut <dyn erased_serde::Deserializer>::erase(&mut deserializer))?;
+///         deserializer.end()?;
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait FormatBackend: std::fmt::Debug + Send + Sync {
+    /// Name of the format, used to select it with `Format::Custom`
+    fn name(&self) -> &'static str;
+
+    /// Lowercase file extensions, without the leading `.`, used by `Format::Auto`
+    fn extensions(&self) -> &[&'static str];
+
+    /// Parses `input`, calling `visit` once with a deserializer for it
+    fn deserialize(&self, input: &str, visit: &mut Visit) -> std::result::Result<(), BoxError>;
+}
+
+/// The formats registered with a Builder
+#[derive(Debug, Clone)]
+pub(crate) struct Formats {
+    backends: Vec<Arc<dyn FormatBackend>>,
+}
+
+impl Formats {
+    pub(crate) fn register(&mut self, backend: Arc<dyn FormatBackend>) {
+        self.backends.push(backend);
+    }
+
+    pub(crate) fn by_name(&self, name: &str) -> Option<&dyn FormatBackend> {
+        self.backends
+            .iter()
+            .rev()
+            .find(|backend| backend.name() == name)
+            .map(|backend| backend.as_ref())
+    }
+
+    pub(crate) fn by_extension(&self, extension: &str) -> Option<&dyn FormatBackend> {
+        self.backends
+            .iter()
+            .rev()
+            .find(|backend| backend.extensions().contains(&extension))
+            .map(|backend| backend.as_ref())
+    }
+}
+
+impl Default for Formats {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut formats = Self { backends: vec![] };
+
+        #[cfg(feature = "ron")]
+        formats.register(Arc::new(Ron));
+        #[cfg(feature = "json5")]
+        formats.register(Arc::new(Json5));
+        #[cfg(feature = "ini")]
+        formats.register(Arc::new(Ini));
+
+        formats
+    }
+}
+
+/// Deserializes `T` from `input` using `backend`
+pub(crate) fn deserialize<T>(backend: &dyn FormatBackend, input: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let mut value = None;
+
+    backend
+        .deserialize(input, &mut |deserializer| {
+            value = Some(erased_serde::deserialize(deserializer)?);
+            Ok(())
+        })
+        .map_err(|wrapped| Error::FormatError {
+            format: backend.name().to_string(),
+            wrapped,
+        })?;
+
+    value.ok_or_else(|| Error::FormatError {
+        format: backend.name().to_string(),
+        wrapped: "Format backend didn't provide a deserializer".into(),
+    })
+}
+
+/// [RON](https://github.com/ron-rs/ron) format, for `.ron` files
+///
+/// The `implicit_some` extension is enabled, so optional values needn't be wrapped in `Some`.
+#[cfg(feature = "ron")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ron;
+
+#[cfg(feature = "ron")]
+impl FormatBackend for Ron {
+    fn name(&self) -> &'static str {
+        "ron"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["ron"]
+    }
+
+    fn deserialize(&self, input: &str, visit: &mut Visit) -> std::result::Result<(), BoxError> {
+        // Every field of a Layer is optional, so don't require them to be wrapped in `Some`
+        let options = ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+        let mut deserializer = ron::Deserializer::from_str_with_options(input, options)?;
+        visit(&mut <dyn erased_serde::Deserializer>::erase(
+            &mut deserializer,
+        ))?;
+        deserializer.end()?;
+        Ok(())
+    }
+}
+
+/// [JSON5](https://json5.org) format, for `.json5` files
+#[cfg(feature = "json5")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json5;
+
+#[cfg(feature = "json5")]
+impl FormatBackend for Json5 {
+    fn name(&self) -> &'static str {
+        "json5"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["json5"]
+    }
+
+    fn deserialize(&self, input: &str, visit: &mut Visit) -> std::result::Result<(), BoxError> {
+        let mut deserializer = json5::Deserializer::from_str(input)?;
+        visit(&mut <dyn erased_serde::Deserializer>::erase(
+            &mut deserializer,
+        ))?;
+        Ok(())
+    }
+}
+
+/// INI format, for `.ini` files
+///
+/// Keys outside of a section set top level fields, and sections set subconfig fields,
+/// with dotted section names addressing nested subconfigs. Values are parsed into each
+/// field's type, as with environment variables.
+#[cfg(feature = "ini")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ini;
+
+#[cfg(feature = "ini")]
+impl FormatBackend for Ini {
+    fn name(&self) -> &'static str {
+        "ini"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["ini"]
+    }
+
+    fn deserialize(&self, input: &str, visit: &mut Visit) -> std::result::Result<(), BoxError> {
+        let ini = ini::Ini::load_from_str(input)?;
+
+        let mut entries = vec![];
+        for (section, properties) in ini.iter() {
+            let section = section
+                .map(|section| section.split('.').map(str::to_string).collect())
+                .unwrap_or_else(Vec::new);
+            for (key, value) in properties.iter() {
+                let mut path = section.clone();
+                path.push(key.to_string());
+                entries.push((path.clone(), path.join("."), value.to_string()));
+            }
+        }
+
+        let deserializer = crate::env::tree(entries)?;
+        visit(&mut <dyn erased_serde::Deserializer>::erase(deserializer))?;
+        Ok(())
+    }
+}
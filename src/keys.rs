@@ -0,0 +1,318 @@
+//! Detects keys in config files and strings that aren't fields of the Config
+
+use std::{fmt, sync::Arc};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::{
+    location,
+    merge::{Variants, UNSET_ALL},
+    meta::{StructMeta, ValueKind},
+    Location,
+};
+
+/// A key in a file or string Layer that isn't a field of the Config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKey {
+    /// Dotted path of the key, e.g. `db.urii`
+    pub path: String,
+    /// The closest field key, if one is close enough to be a typo
+    pub suggestion: Option<String>,
+    /// Where the key is
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean {}?)", suggestion)?;
+        }
+        write!(f, "{}", location::at(self.location.as_ref()))
+    }
+}
+
+/// Called with each unknown key in Layers that aren't strict
+pub(crate) type OnUnknownKey = Arc<dyn Fn(&UnknownKey) + Send + Sync>;
+
+/// How a Builder treats unknown keys
+#[derive(Clone, Default)]
+pub(crate) struct UnknownKeys {
+    pub(crate) strict: bool,
+    pub(crate) on_unknown_key: Option<OnUnknownKey>,
+}
+
+impl UnknownKeys {
+    /// Whether keys have to be checked, for Layers that are `strict` or not
+    pub(crate) fn check(&self, strict: bool) -> bool {
+        strict || self.strict || self.on_unknown_key.is_some()
+    }
+}
+
+impl fmt::Debug for UnknownKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnknownKeys")
+            .field("strict", &self.strict)
+            .field("on_unknown_key", &self.on_unknown_key.is_some())
+            .finish()
+    }
+}
+
+/// The keys of a config file or string, in the order they're written
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Keys(Vec<(String, Option<Keys>)>);
+
+impl Keys {
+    /// Finds the keys that aren't fields of `meta`, or of its subconfigs, or of the
+    /// `variants` selected of its enums
+    pub(crate) fn unknown_into(
+        &self,
+        meta: &StructMeta,
+        prefix: &str,
+        variants: &Variants,
+        unknown: &mut Vec<(String, Option<String>)>,
+    ) {
+        let known = meta.keys(variants.selected(meta, prefix));
+        // Keys of flattened subconfigs are checked against their fields, and any other
+        // flattened field takes every key
+        let flattened = meta
+            .fields
+            .iter()
+            .filter(|field| field.flatten)
+            .filter_map(|field| match field.kind {
+                ValueKind::Subconfig(sub_meta) => Some(sub_meta()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let takes_any = meta
+            .fields
+            .iter()
+            .any(|field| field.flatten && !matches!(field.kind, ValueKind::Subconfig(_)));
+        for (key, value) in &self.0 {
+            let path = crate::field_path(prefix, key);
+            if key == "_unset" {
+                continue;
+            }
+            if !known.contains(&key.as_str()) {
+                let sub_meta = flattened
+                    .iter()
+                    .find(|sub_meta| sub_meta.keys(None).contains(&key.as_str()));
+                if let Some(sub_meta) = sub_meta {
+                    let keys = Keys(vec![(key.clone(), value.clone())]);
+                    keys.unknown_into(sub_meta, prefix, variants, unknown);
+                } else if !takes_any {
+                    let flattened_keys = flattened.iter().flat_map(|sub_meta| sub_meta.keys(None));
+                    let keys = known.iter().copied().chain(flattened_keys);
+                    unknown.push((path, suggest(key, keys).map(str::to_string)));
+                }
+                continue;
+            }
+            let field = meta.fields.iter().find(|field| field.has_key(key));
+            match (field.map(|field| &field.kind), value) {
+                (Some(ValueKind::Subconfig(sub_meta)), Some(keys)) => {
+                    keys.unknown_into(&sub_meta(), &path, variants, unknown);
+                }
+                (Some(ValueKind::Array(kind) | ValueKind::Map(kind)), Some(keys)) => {
+                    if let ValueKind::Subconfig(sub_meta) = **kind {
+                        keys.entries_unknown_into(&sub_meta(), &path, variants, unknown);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Finds the keys that aren't fields of `meta` in each entry of a list or map of
+    /// subconfigs
+    fn entries_unknown_into(
+        &self,
+        meta: &StructMeta,
+        prefix: &str,
+        variants: &Variants,
+        unknown: &mut Vec<(String, Option<String>)>,
+    ) {
+        for (key, value) in &self.0 {
+            if let Some(keys) = value {
+                keys.unknown_into(meta, &crate::field_path(prefix, key), variants, unknown);
+            }
+        }
+    }
+}
+
+/// Checks that a path listed in `_unset` names a field of `meta`, or a field or entry within
+/// one, returning the closest key to the first part that doesn't if it's close enough
+pub(crate) fn unknown_unset(meta: &StructMeta, path: &str) -> Option<Option<String>> {
+    let mut meta = meta.clone();
+    let mut segments = path.split('.');
+    while let Some(segment) = segments.next() {
+        if segment == UNSET_ALL {
+            return None;
+        }
+        let field = match meta.fields.iter().find(|field| field.has_key(segment)) {
+            Some(field) => field.clone(),
+            None => {
+                let keys = meta.fields.iter().map(|field| field.key);
+                return Some(suggest(segment, keys).map(str::to_string));
+            }
+        };
+        meta = match field.kind {
+            ValueKind::Subconfig(sub_meta) => sub_meta(),
+            // Any entry of a collection of subconfigs can be unset, and fields within it
+            ValueKind::Array(kind) | ValueKind::Map(kind) => match (*kind, segments.next()) {
+                (ValueKind::Subconfig(sub_meta), Some(_)) => sub_meta(),
+                (ValueKind::Subconfig(_), None) => return None,
+                (_, next) => return next.map(|_| None),
+            },
+            // Other values are unset as a whole
+            _ => return segments.next().map(|_| None),
+        };
+    }
+    None
+}
+
+/// Returns the name closest to `key`, if it's close enough to be a typo
+fn suggest<'a>(key: &str, names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(1);
+    names
+        .map(|name| (distance(key, name), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// Levenshtein distance between two strings, counting chars
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(deserializer
+            .deserialize_any(KeysVisitor)?
+            .unwrap_or_default())
+    }
+}
+
+/// Visits any value, returning the keys of maps and `None` for anything else
+struct KeysVisitor;
+
+macro_rules! visit_scalar {
+    ($($method:ident: $ty:ty),*) => {
+        $(
+            fn $method<E: de::Error>(self, _: $ty) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+        )*
+    };
+}
+
+impl<'de> Visitor<'de> for KeysVisitor {
+    type Value = Option<Keys>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    visit_scalar!(
+        visit_bool: bool,
+        visit_i64: i64,
+        visit_u64: u64,
+        visit_i128: i128,
+        visit_u128: u128,
+        visit_f64: f64,
+        visit_str: &str,
+        visit_bytes: &[u8]
+    );
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    /// Lists have their indexes as keys, for lists of subconfigs
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut keys = vec![];
+        while let Some(value) = seq.next_element_seed(KeysSeed)? {
+            keys.push((keys.len().to_string(), value));
+        }
+        Ok(Some(Keys(keys)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = vec![];
+        while let Some(key) = map.next_key::<Key>()? {
+            let value = map.next_value_seed(KeysSeed)?;
+            keys.push((key.0, value));
+        }
+        Ok(Some(Keys(keys)))
+    }
+}
+
+struct KeysSeed;
+
+impl<'de> de::DeserializeSeed<'de> for KeysSeed {
+    type Value = Option<Keys>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(KeysVisitor)
+    }
+}
+
+/// A map key, which may be a string, number or bool
+struct Key(String);
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = Key;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map key")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Key, E> {
+                Ok(Key(value.to_string()))
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Key, E> {
+                Ok(Key(value.to_string()))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Key, E> {
+                Ok(Key(value.to_string()))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Key, E> {
+                Ok(Key(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
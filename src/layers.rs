@@ -1,21 +1,34 @@
 //! Builds a Config from layers
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     env::current_dir,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
+#[cfg(feature = "clap")]
 use clap::Parser;
 
-use crate::{map_canonicalization_error, map_io_error, Error};
+use crate::{
+    env,
+    formats::{self, FormatBackend, Formats},
+    keys::{self, Keys, UnknownKey, UnknownKeys},
+    location::{self, Location},
+    map_canonicalization_error, map_io_error, merge,
+    provenance::{Provenance, Recorder, ValueSource},
+    Error,
+};
 
-use super::{LayeredConfLayer, LayeredConfMerge, LayeredConfSolid, LayeredConfSolidify, Result};
+use super::{
+    LayeredConfArgs, LayeredConfLayer, LayeredConfMerge, LayeredConfSolid, LayeredConfSolidify,
+    Result,
+};
 
 /// Builds a layered configuration
 ///
-/// ```rust
+#[cfg_attr(feature = "yaml", doc = "```rust")]
+#[cfg_attr(not(feature = "yaml"), doc = "```rust,ignore")]
 /// use std::path::PathBuf;
 ///
 /// use layeredconf::{Builder, Format, LayeredConf, Source};
@@ -43,12 +56,12 @@ where
         + std::fmt::Debug
         + Default
         + serde::de::DeserializeOwned
-        + clap::FromArgMatches
-        + clap::IntoApp
-        + clap::Parser
+        + LayeredConfArgs
         + Sized,
 {
     layers: Vec<Arc<Layer<TSolid>>>,
+    formats: Formats,
+    unknown_keys: UnknownKeys,
 }
 
 impl<TSolid> Builder<TSolid>
@@ -60,30 +73,141 @@ where
         + std::fmt::Debug
         + Default
         + serde::de::DeserializeOwned
-        + clap::FromArgMatches
-        + clap::IntoApp
-        + clap::Parser
+        + LayeredConfArgs
         + Sized,
 {
     /// Returns a new Builder
     pub fn new() -> Self {
-        Self { layers: vec![] }
+        Self {
+            layers: vec![],
+            formats: Formats::default(),
+            unknown_keys: UnknownKeys::default(),
+        }
+    }
+
+    /// Registers a further config file format
+    ///
+    /// Files with one of its extensions are loaded with it when using `Format::Auto`, and it
+    /// can be selected with `Format::Custom(name)`. Formats registered later take precedence.
+    pub fn register_format<TBackend>(&mut self, backend: TBackend) -> &mut Self
+    where
+        TBackend: FormatBackend + 'static,
+    {
+        self.formats.register(Arc::new(backend));
+        self
     }
 
     /// Adds a new Layer to the Builder from a source
     pub fn new_layer(&mut self, source: Source) -> &mut Self {
-        let layer = Arc::from(Layer::new(source, None, vec![]));
+        let layer = Arc::from(Layer::new(source, None, vec![], false));
+        self.layers.push(layer);
+        self
+    }
+
+    /// Adds a new Layer to the Builder from a source, rejecting unknown keys as with `strict`
+    pub fn new_strict_layer(&mut self, source: Source) -> &mut Self {
+        let layer = Arc::from(Layer::new(source, None, vec![], true));
         self.layers.push(layer);
         self
     }
 
+    /// Rejects keys that aren't fields of the Config in every file and string Layer
+    ///
+    /// Loading fails with `Error::UnknownKeys`, naming each key with where it is, and the
+    /// field it's probably a typo of. Files loaded with `load_config` are strict when the
+    /// Layer loading them is. Keys are matched against serde's keys for fields, so with
+    /// `#[serde(rename_all = "kebab-case")]` `pool-size` is known and `pool_size` isn't. The
+    /// fields of an enum are those of the variant its tag selects in the same Layer.
+    ///
+    #[cfg_attr(feature = "yaml", doc = "```rust")]
+    #[cfg_attr(not(feature = "yaml"), doc = "```rust,ignore")]
+    /// use layeredconf::{Builder, Error, Format, LayeredConf, Source};
+    ///
+    /// #[derive(LayeredConf, serde::Deserialize, Debug)]
+    /// struct Config {
+    ///     #[clap(long)]
+    ///     database: Option<String>,
+    /// }
+    ///
+    /// let error = Builder::<Config>::new()
+    ///     .strict(true)
+    ///     .new_layer(Source::String("databse: db".to_string(), Format::Yaml))
+    ///     .solidify()
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "Unknown keys databse (did you mean database?) at <string>:1:1"
+    /// );
+    /// ```
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.unknown_keys.strict = strict;
+        self
+    }
+
+    /// Calls `on_unknown_key` with each unknown key in Layers that aren't strict, e.g. to
+    /// log a warning
+    pub fn on_unknown_key<F>(&mut self, on_unknown_key: F) -> &mut Self
+    where
+        F: Fn(&UnknownKey) + Send + Sync + 'static,
+    {
+        self.unknown_keys.on_unknown_key = Some(Arc::new(on_unknown_key));
+        self
+    }
+
     /// Solidifies the Builder ingo a Config
+    ///
+    /// Validation failures of values set by a Layer carry the Location they were set at.
     pub fn solidify(&self) -> Result<TSolid> {
+        let mut merged = self.merged_layer()?;
+        merged.merge_from(&<TSolid>::Layer::default_layer());
+
+        merged.solidify().map_err(|error| match error {
+            Error::ValidationFailed { mut failures } => {
+                for failure in &mut failures {
+                    failure.location = self.locate(&failure.path);
+                }
+                Error::ValidationFailed { failures }
+            }
+            error => error,
+        })
+    }
+
+    /// Returns where the value at `path` was set, by the highest Layer that sets it and
+    /// isn't below a Layer unsetting it
+    fn locate(&self, path: &str) -> Option<Location> {
+        let mut unset = vec![];
+        let mut variants = merge::Variants::default();
+        let mut location = None;
+
+        for layer in self.layers.iter().rev() {
+            layer.for_each(&mut |layer, obj| {
+                let switched = variants.select(obj.variants(), &unset);
+                if location.is_none()
+                    && !merge::is_unset_path(&unset, path)
+                    && !switched
+                        .iter()
+                        .any(|enum_path| merge::within(path, enum_path))
+                    && obj.set_fields().iter().any(|field| field == path)
+                {
+                    location = Some(layer.location(path));
+                }
+                merge::extend_unset(&mut unset, &obj.unset_fields());
+            });
+        }
+
+        location.flatten()
+    }
+
+    /// Loads and merges all Layers, without filling in defaults
+    ///
+    /// Fields no Layer set are `None`, so this is the configuration as given by the sources.
+    pub fn merged_layer(&self) -> Result<<TSolid>::Layer> {
         if self.layers.is_empty() {
             return Err(Error::SolidifyFailedNoLayers);
         }
         for layer in &self.layers {
-            layer.load()?;
+            layer.load(&self.formats, &self.unknown_keys)?;
         }
 
         let mut merged = <TSolid>::Layer::default();
@@ -92,9 +216,101 @@ where
             layer.merge_into(&mut merged)?;
         }
 
-        merged.merge_from(&<TSolid>::Layer::default_layer());
+        merged.clear_unset();
 
-        merged.solidify()
+        Ok(merged)
+    }
+
+    /// Serializes the merged Layers in `format`, e.g. for a `--print-config` option
+    ///
+    /// With `with_defaults`, fields no Layer set are filled in from the defaults. Fields
+    /// without a value are left out. Only JSON, TOML and YAML are supported.
+    ///
+    #[cfg_attr(all(feature = "json", feature = "yaml"), doc = "```rust")]
+    #[cfg_attr(not(all(feature = "json", feature = "yaml")), doc = "```rust,ignore")]
+    /// use layeredconf::{Builder, Format, LayeredConf, Source};
+    ///
+    /// #[derive(LayeredConf, serde::Deserialize, serde::Serialize)]
+    /// struct Config {
+    ///     #[clap(long)]
+    ///     name: String,
+    ///     #[layered(default)]
+    ///     #[clap(long)]
+    ///     port: u16,
+    /// }
+    ///
+    /// fn main() -> anyhow::Result<()> {
+    ///     let dumped = Builder::<Config>::new()
+    ///         .new_layer(Source::String("name: test".to_string(), Format::Yaml))
+    ///         .dump(Format::Json, true)?;
+    ///
+    ///     assert_eq!(dumped, "{\n  \"name\": \"test\",\n  \"port\": 0\n}");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn dump(&self, format: Format, with_defaults: bool) -> Result<String>
+    where
+        TSolid::Layer: serde::Serialize,
+    {
+        let mut merged = self.merged_layer()?;
+        if with_defaults {
+            merged.merge_from(&<TSolid>::Layer::default_layer());
+            merged.fill_optional_defaults();
+        }
+
+        match format {
+            #[cfg(feature = "json")]
+            Format::Json => Ok(serde_json::to_string_pretty(&merged)?),
+            // Converting to a Value first writes fields before tables, as TOML requires
+            #[cfg(feature = "toml")]
+            Format::Toml => Ok(toml::to_string_pretty(&toml::Value::try_from(&merged)?)?),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Ok(serde_yaml::to_string(&merged)?),
+            format => Err(Error::DumpUnsupported { format }),
+        }
+    }
+
+    /// Solidifies the Builder into a Config, along with the source of each value
+    pub fn solidify_with_provenance(&self) -> Result<(TSolid, Provenance)> {
+        let solid = self.solidify()?;
+
+        let mut recorder = Recorder::default();
+
+        for layer in self.layers.iter().rev() {
+            layer.for_each(&mut |layer, obj| recorder.record(obj, layer.value_source()));
+        }
+
+        let defaults = <TSolid>::Layer::default_layer();
+        let mut filled = self.merged_layer()?;
+        filled.merge_from(&defaults);
+        filled.fill_optional_defaults();
+
+        Ok((solid, recorder.finish(&defaults, &filled)))
+    }
+
+    /// A Builder with the same Sources and formats, whose Layers are loaded afresh
+    #[cfg(any(feature = "watch", all(feature = "signal", unix)))]
+    pub(crate) fn reloadable(&self) -> Self {
+        Self {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| Arc::new(Layer::new(layer.source.clone(), None, vec![], layer.strict)))
+                .collect(),
+            formats: self.formats.clone(),
+            unknown_keys: self.unknown_keys.clone(),
+        }
+    }
+
+    /// Paths of the files loaded by the Layers, including `load_config` files
+    #[cfg(feature = "watch")]
+    pub(crate) fn file_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = vec![];
+        for layer in &self.layers {
+            layer.file_paths_into(&mut paths)?;
+        }
+        Ok(paths)
     }
 }
 
@@ -107,9 +323,7 @@ where
         + std::fmt::Debug
         + Default
         + serde::de::DeserializeOwned
-        + clap::FromArgMatches
-        + clap::IntoApp
-        + clap::Parser
+        + LayeredConfArgs
         + Sized,
 {
     fn default() -> Self {
@@ -127,18 +341,45 @@ where
         + std::fmt::Debug
         + Default
         + serde::de::DeserializeOwned
-        + clap::FromArgMatches
-        + clap::IntoApp
-        + clap::Parser
+        + LayeredConfArgs
         + Sized,
 {
     source: Source,
     cwd: Option<PathBuf>,
     parents: Vec<Source>,
+    strict: bool,
     obj: Mutex<<TSolid>::Layer>,
+    loaded: Mutex<Option<Loaded>>,
     sub_layers: Mutex<Vec<Layer<TSolid>>>,
 }
 
+/// What a Layer was loaded from, to locate its values
+#[derive(Debug)]
+struct Loaded {
+    /// The Source, with the canonical path for files
+    origin: Source,
+    /// The text of files and strings
+    text: Option<Arc<str>>,
+}
+
+impl Loaded {
+    /// Returns where the key at `path` is
+    fn locate(&self, path: &str) -> Location {
+        let origin = self.origin.clone();
+        let format = match &self.origin {
+            Source::File(_, format) | Source::String(_, format) => *format,
+            _ => Format::Auto,
+        };
+        match &self.text {
+            Some(text) => match location::locate(text, format, path) {
+                Some(span) => Location::spanning(origin, text.clone(), span),
+                None => Location::new(origin, Some(text.clone()), None),
+            },
+            None => Location::new(origin, None, None),
+        }
+    }
+}
+
 impl<TSolid> Layer<TSolid>
 where
     TSolid: LayeredConfSolid,
@@ -148,17 +389,17 @@ where
         + std::fmt::Debug
         + Default
         + serde::de::DeserializeOwned
-        + clap::FromArgMatches
-        + clap::IntoApp
-        + clap::Parser
+        + LayeredConfArgs
         + Sized,
 {
-    fn new(source: Source, cwd: Option<PathBuf>, parents: Vec<Source>) -> Self {
+    fn new(source: Source, cwd: Option<PathBuf>, parents: Vec<Source>, strict: bool) -> Self {
         Self {
             source,
             cwd,
             parents,
+            strict,
             obj: Mutex::from(<TSolid>::Layer::default()),
+            loaded: Mutex::from(None),
             sub_layers: Mutex::from(Vec::new()),
         }
     }
@@ -174,32 +415,121 @@ where
         Ok(())
     }
 
-    pub fn load(&self) -> super::Result<()> {
+    /// Calls `f` with this Layer and its sub Layers, highest first, as they're merged
+    fn for_each(&self, f: &mut impl FnMut(&Self, &<TSolid>::Layer)) {
+        f(self, &self.obj.lock().unwrap());
+        for sub_layer in self.sub_layers.lock().unwrap().iter().rev() {
+            sub_layer.for_each(f);
+        }
+    }
+
+    fn value_source(&self) -> ValueSource {
+        ValueSource::Layer {
+            source: self.source.clone(),
+            parents: self.parents.clone(),
+        }
+    }
+
+    /// Returns where the value at the key path `path` is in what this Layer was loaded from
+    fn location(&self, path: &str) -> Option<Location> {
+        let loaded = self.loaded.lock().unwrap();
+        loaded.as_ref().map(|loaded| loaded.locate(path))
+    }
+
+    #[cfg(feature = "watch")]
+    fn file_paths_into(&self, paths: &mut Vec<PathBuf>) -> Result<()> {
+        if let Source::File(path, _) | Source::FileOptional(path, _) = &self.source {
+            paths.push(if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                self.get_cwd()?.join(path)
+            });
+        }
+        for sub_layer in self.sub_layers.lock().unwrap().iter() {
+            sub_layer.file_paths_into(paths)?;
+        }
+        Ok(())
+    }
+
+    pub fn load(&self, formats: &Formats, unknown_keys: &UnknownKeys) -> super::Result<()> {
         let mut seen_paths = HashSet::new();
-        self.load_impl(&mut seen_paths)
+        self.load_impl(formats, unknown_keys, &mut seen_paths)
     }
 
-    fn load_impl(&self, seen_paths: &mut HashSet<PathBuf>) -> super::Result<()> {
+    fn load_impl(
+        &self,
+        formats: &Formats,
+        unknown_keys: &UnknownKeys,
+        seen_paths: &mut HashSet<PathBuf>,
+    ) -> super::Result<()> {
         let mut obj = self.obj.lock().unwrap();
+        let mut loaded = self.loaded.lock().unwrap();
         let mut sub_layers = self.sub_layers.lock().unwrap();
 
-        *obj = match &self.source {
-            Source::File(path, format) => self.load_file(path, format, seen_paths)?,
-            Source::FileOptional(path, format) => match self.load_file(path, format, seen_paths) {
-                Err(Error::FileNotFound { .. }) => <TSolid>::Layer::default(),
-                Err(error) => {
-                    return Err(error);
+        let text = match &self.source {
+            Source::String(string, _) => Some(Arc::from(string.as_str())),
+            _ => None,
+        };
+        let from_source = || {
+            Some(Loaded {
+                origin: self.source.clone(),
+                text: text.clone(),
+            })
+        };
+        let with_origin = |error: Error| error.with_origin(&self.source, text.as_ref());
+
+        (*obj, *loaded) = match &self.source {
+            Source::File(path, format) => self.load_file(path, format, formats, seen_paths)?,
+            Source::FileOptional(path, format) => {
+                match self.load_file(path, format, formats, seen_paths) {
+                    Err(Error::FileNotFound { .. }) => (<TSolid>::Layer::default(), None),
+                    Err(error) => {
+                        return Err(error);
+                    }
+                    Ok(value) => value,
                 }
-                Ok(value) => value,
-            },
-            Source::String(string, format) => self.load_string(string, format)?,
-            Source::Environment(_) => {
-                unimplemented!();
             }
-            Source::Arguments => <TSolid>::Layer::parse(),
-            Source::ArgumentsFrom(from) => <TSolid>::Layer::parse_from(from),
+            Source::String(string, format) => {
+                // Strings are located with the parser of the format they're detected as
+                let format = match format {
+                    Format::Auto => self.detect_format(None, string).map_err(with_origin)?,
+                    format => *format,
+                };
+                let obj = self
+                    .load_string(string, &format, formats)
+                    .map_err(with_origin)?;
+                let loaded = Loaded {
+                    origin: Source::String(string.clone(), format),
+                    text: text.clone(),
+                };
+                (obj, Some(loaded))
+            }
+            Source::Environment(prefix) => (
+                env::from_vars(prefix.as_deref(), env::vars())?,
+                from_source(),
+            ),
+            Source::EnvironmentFrom(prefix, vars) => (
+                env::from_vars(prefix.as_deref(), vars.clone())?,
+                from_source(),
+            ),
+            Source::EnvironmentBindings => {
+                (<TSolid>::Layer::env_layer(&env::vars())?, from_source())
+            }
+            Source::EnvironmentBindingsFrom(vars) => {
+                (<TSolid>::Layer::env_layer(vars)?, from_source())
+            }
+            #[cfg(feature = "clap")]
+            Source::Arguments => (<TSolid>::Layer::parse(), from_source()),
+            #[cfg(feature = "clap")]
+            Source::ArgumentsFrom(from) => (<TSolid>::Layer::parse_from(from), from_source()),
         };
 
+        if let Some(loaded) = &*loaded {
+            if unknown_keys.check(self.strict) {
+                self.check_keys(&obj, loaded, formats, unknown_keys)?;
+            }
+        }
+
         let source_dir = self.get_source_dir()?;
 
         *sub_layers = obj
@@ -214,17 +544,65 @@ where
                     Source::File(path, Format::Auto),
                     Some(source_dir.clone()),
                     parents,
+                    self.strict,
                 )
             })
             .collect();
 
         for sub_layer in sub_layers.iter() {
-            sub_layer.load_impl(seen_paths)?;
+            sub_layer.load_impl(formats, unknown_keys, seen_paths)?;
         }
 
         Ok(())
     }
 
+    /// Finds keys of a file or string, and paths the Layer unsets, that aren't fields of the
+    /// Config, failing if the Layer is strict, or calling `on_unknown_key` otherwise
+    fn check_keys(
+        &self,
+        obj: &<TSolid>::Layer,
+        loaded: &Loaded,
+        formats: &Formats,
+        unknown_keys: &UnknownKeys,
+    ) -> Result<()> {
+        let meta = <TSolid>::Layer::meta();
+        let mut unknown = vec![];
+
+        if let (Some(text), Source::File(_, format) | Source::String(_, format)) =
+            (&loaded.text, &loaded.origin)
+        {
+            let keys: Keys = self.load_string(text, format, formats)?;
+            // Fields are checked against the variants of enums this Layer selects
+            let mut variants = merge::Variants::default();
+            variants.select(obj.variants(), &[]);
+            keys.unknown_into(&meta, "", &variants, &mut unknown);
+        }
+        for path in obj.unset_fields() {
+            if let Some(suggestion) = keys::unknown_unset(&meta, &path) {
+                unknown.push((path, suggestion));
+            }
+        }
+        let unknown = unknown
+            .into_iter()
+            .map(|(path, suggestion)| UnknownKey {
+                location: Some(loaded.locate(&path)),
+                path,
+                suggestion,
+            })
+            .collect::<Vec<_>>();
+
+        if unknown.is_empty() {
+            Ok(())
+        } else if self.strict || unknown_keys.strict {
+            Err(Error::UnknownKeys { unknown })
+        } else {
+            if let Some(on_unknown_key) = &unknown_keys.on_unknown_key {
+                unknown.iter().for_each(|key| on_unknown_key(key));
+            }
+            Ok(())
+        }
+    }
+
     fn get_source_dir(&self) -> Result<PathBuf> {
         use Source::{File, FileOptional};
 
@@ -250,8 +628,9 @@ where
         &self,
         path: &Path,
         format: &Format,
+        formats: &Formats,
         seen_paths: &mut HashSet<PathBuf>,
-    ) -> Result<<TSolid>::Layer> {
+    ) -> Result<(<TSolid>::Layer, Option<Loaded>)> {
         let path = if path.is_absolute() {
             path.to_path_buf()
         } else {
@@ -268,8 +647,21 @@ where
         seen_paths.insert(path.clone());
 
         let string = std::fs::read_to_string(&path).map_err(map_io_error(&path))?;
-
-        self.load_string(&string, &self.auto_format(&path, format)?)
+        let text = Arc::from(string.as_str());
+
+        let format = self.auto_format(&path, &string, format, formats)?;
+        let origin = Source::File(path, format);
+        let obj = self
+            .load_string(&string, &format, formats)
+            .map_err(|error| error.with_origin(&origin, Some(&text)))?;
+
+        Ok((
+            obj,
+            Some(Loaded {
+                origin,
+                text: Some(text),
+            }),
+        ))
     }
 
     fn get_cwd(&self) -> Result<PathBuf> {
@@ -280,44 +672,152 @@ where
             .map_err(|wrapped| Error::CurrentDir { wrapped })
     }
 
-    fn load_string(&self, string: &str, format: &Format) -> Result<<TSolid>::Layer> {
+    fn load_string<T>(&self, string: &str, format: &Format, formats: &Formats) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
         Ok(match format {
-            Format::Auto => return Err(Error::AutoFormatFailed),
+            Format::Auto => {
+                self.load_string(string, &self.detect_format(None, string)?, formats)?
+            }
+            #[cfg(feature = "json")]
             Format::Json => serde_json::from_str(string)?,
+            #[cfg(feature = "toml")]
             Format::Toml => toml::from_str(string)?,
+            #[cfg(feature = "yaml")]
             Format::Yaml => serde_yaml::from_str(string)?,
+            Format::Custom(name) => {
+                let backend = formats.by_name(name).ok_or_else(|| Error::UnknownFormat {
+                    name: name.to_string(),
+                })?;
+                formats::deserialize(backend, string)?
+            }
         })
     }
 
-    fn auto_format(&self, path: &Path, format: &Format) -> Result<Format> {
+    fn auto_format(
+        &self,
+        path: &Path,
+        string: &str,
+        format: &Format,
+        formats: &Formats,
+    ) -> Result<Format> {
         match format {
             Format::Auto => {
-                let extension = path.extension().map(|s| s.to_str()).flatten();
-                match extension {
-                    Some("json") => Ok(Format::Json),
-                    Some("toml") => Ok(Format::Toml),
-                    Some("yaml") => Ok(Format::Yaml),
-                    _ => Err(Error::UnknownExtension {
-                        extension: extension.map(|s| s.to_string()),
-                    }),
+                let file_name = path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or_default()
+                    .to_lowercase();
+
+                // Check extensions from the last, so `config.yaml.example` is yaml
+                let extensions = file_name.split('.').skip(1).collect::<Vec<_>>();
+                for extension in extensions.into_iter().rev() {
+                    match extension {
+                        #[cfg(feature = "json")]
+                        "json" | "jsn" => return Ok(Format::Json),
+                        #[cfg(feature = "toml")]
+                        "toml" => return Ok(Format::Toml),
+                        #[cfg(feature = "yaml")]
+                        "yaml" | "yml" => return Ok(Format::Yaml),
+                        extension => {
+                            if let Some(backend) = formats.by_extension(extension) {
+                                return Ok(Format::Custom(backend.name()));
+                            }
+                        }
+                    }
                 }
+
+                self.detect_format(Some(path), string).map_err(|error| {
+                    match (error, path.extension()) {
+                        (Error::AutoFormatFailed, Some(extension)) => Error::UnknownExtension {
+                            extension: Some(extension.to_string_lossy().to_string()),
+                        },
+                        (error, _) => error,
+                    }
+                })
             }
             format => Ok(*format),
         }
     }
+
+    fn detect_format(&self, path: Option<&Path>, string: &str) -> Result<Format> {
+        #[cfg(feature = "json")]
+        if matches!(
+            serde_json::from_str(string),
+            Ok(serde_json::Value::Object(_))
+        ) {
+            return Ok(Format::Json);
+        }
+
+        let detected = [detect_toml(string), detect_yaml(string)];
+        let candidates = detected
+            .iter()
+            .flatten()
+            .filter(|(_, empty)| !empty)
+            .map(|(format, _)| *format)
+            .collect::<Vec<_>>();
+        // An empty toml file only contains whitespace and comments, which are valid yaml
+        let fallback = detected
+            .iter()
+            .flatten()
+            .find(|(_, empty)| *empty)
+            .map(|(format, _)| *format);
+
+        match candidates.as_slice() {
+            [] => fallback.ok_or(Error::AutoFormatFailed),
+            [format] => Ok(*format),
+            _ => Err(Error::AutoFormatAmbiguous {
+                path: path.map(Path::to_path_buf),
+                candidates,
+            }),
+        }
+    }
+}
+
+/// TOML, if `string` is a TOML table, and whether the table is empty
+#[cfg(feature = "toml")]
+fn detect_toml(string: &str) -> Option<(Format, bool)> {
+    let table = toml::from_str::<toml::value::Table>(string).ok()?;
+    Some((Format::Toml, table.is_empty()))
+}
+
+#[cfg(not(feature = "toml"))]
+fn detect_toml(_string: &str) -> Option<(Format, bool)> {
+    None
+}
+
+/// YAML, if `string` is a YAML mapping
+#[cfg(feature = "yaml")]
+fn detect_yaml(string: &str) -> Option<(Format, bool)> {
+    match serde_yaml::from_str(string) {
+        Ok(serde_yaml::Value::Mapping(_)) => Some((Format::Yaml, false)),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "yaml"))]
+fn detect_yaml(_string: &str) -> Option<(Format, bool)> {
+    None
 }
 
 /// Config file format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Format {
-    /// Automatically detect config file format from it's extension
+    /// Automatically detect config file format from its extensions, e.g. `.yml` or
+    /// `.yaml.example`, falling back to detecting it from the content
     Auto,
     /// JSON formatted
+    #[cfg(feature = "json")]
     Json,
     /// TOML formatted
+    #[cfg(feature = "toml")]
     Toml,
     /// YAML formatted
+    #[cfg(feature = "yaml")]
     Yaml,
+    /// Formatted with the `FormatBackend` registered with this name
+    Custom(&'static str),
 }
 
 /// Config source
@@ -329,10 +829,23 @@ pub enum Source {
     FileOptional(PathBuf, Format),
     /// From a String
     String(String, Format),
-    /// From process env (currently unimplemented)
+    /// From process env, only using variables starting with the prefix
+    ///
+    /// The prefix is stripped, the remainder lowercased, and `__` separates
+    /// subconfig fields: with a prefix of `MYAPP_`, `MYAPP_DB__URI` sets `db.uri`.
+    /// Fields renamed by serde are matched ignoring case, `-` and `_`, so
+    /// `MYAPP_POOL_SIZE` sets a field renamed to `pool-size`
     Environment(Option<String>),
+    /// From a map of environment variables, as with `Environment`
+    EnvironmentFrom(Option<String>, HashMap<String, String>),
+    /// From process env, only using fields bound with `#[layered(env = "VAR")]`
+    EnvironmentBindings,
+    /// From a map of environment variables, as with `EnvironmentBindings`
+    EnvironmentBindingsFrom(HashMap<String, String>),
     /// From argv
+    #[cfg(feature = "clap")]
     Arguments,
     /// From an Vec of arguments
+    #[cfg(feature = "clap")]
     ArgumentsFrom(Vec<String>),
 }
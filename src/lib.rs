@@ -11,10 +11,25 @@
 //! - Features
 //!
 //! ## Features
-//! - Generate config Layers loaded from multiple sources: files, strings, command line arguments...
+//! - Generate config Layers loaded from multiple sources: files, strings, environment variables, command line arguments...
 //! - Uses Clap to auto-generate command line help + usage info
 //! - Most of Clap's derive features are usable
 //! - Can define futher config files to load within config files, or command line options
+//! - Config structs can be generic, e.g. over the type of a subconfig section shared by several services
+//! - Internally tagged enums, with Layers overriding the selected variant's fields or switching variant
+//! - Maps and `Vec`s of subconfigs, merged key by key, with per-entry defaults and missing fields reported by path, e.g. `upstreams.primary.url`
+//! - Reports which source supplied each value of the final config
+//! - Dumps the merged config as JSON, TOML or YAML, e.g. for a `--print-config` option
+//! - Diffs two configs or Layers by field path, e.g. to log what a reload changed
+//! - Parse and validation errors point to the file, line and column of the offending value
+//! - Opt-in strict mode rejecting unknown keys in config files with "did you mean" suggestions, or warning about them through a callback
+//! - JSON, TOML and YAML formats built in, with RON, JSON5 and INI behind cargo features, and custom formats via `formats::FormatBackend`
+//! - Cargo features `clap`, `json`, `toml` and `yaml` are enabled by default, and can be disabled to drop their dependencies
+//! - JSON Schema of a config's Layers for editor completion, behind the `schema` feature
+//! - Commented example YAML or TOML config files from doc comments, behind the `template` feature
+//! - Reloads the config when its files change, behind the `watch` feature
+//! - Reloads the config on SIGHUP, swapping it atomically, behind the `signal` feature (unix only)
+//! - Renders errors with a snippet of the config and the offending value underlined, via `miette`, behind the `diagnostics` feature
 //!
 //! ## Quick Example
 //!
@@ -22,7 +37,7 @@
 //!
 //! ```toml
 //! [dependencies]
-//! layeredconf = "0.2.0"
+//! layeredconf = "0.3.0"
 //! clap = "3.0.0-beta.5"
 //! serde = { version = "1.0", features = ["derive"] }
 //! ```
@@ -71,13 +86,53 @@
 //! }
 //! ```
 
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+pub mod diff;
+mod env;
+pub mod formats;
+mod keys;
 mod layers;
+mod location;
+#[doc(hidden)]
+pub mod merge;
+#[doc(hidden)]
+pub mod meta;
+mod provenance;
+#[cfg(feature = "schema")]
+mod schema;
+#[cfg(all(feature = "signal", unix))]
+mod signal;
+#[cfg(all(feature = "signal", not(unix)))]
+compile_error!("The `signal` feature needs signals, which are only supported on unix");
+#[cfg(all(feature = "template", any(feature = "toml", feature = "yaml")))]
+mod template;
+#[doc(hidden)]
+pub mod validate;
+#[cfg(feature = "watch")]
+mod watch;
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use thiserror::Error as ThisError;
 
+pub use diff::{Change, LayeredConfDiff};
+pub use keys::UnknownKey;
 pub use layers::{Builder, Format, Source};
+pub use location::Location;
+pub use provenance::{Provenance, ValueSource};
+#[cfg(feature = "schema")]
+pub use schema::LayeredConfSchema;
+#[cfg(all(feature = "signal", unix))]
+pub use signal::SignalReload;
+#[cfg(all(feature = "template", any(feature = "toml", feature = "yaml")))]
+pub use template::LayeredConfTemplate;
+pub use validate::ValidationFailure;
+#[cfg(feature = "watch")]
+pub use watch::Watch;
 
 /// LayeredConf Derive Macro
 ///
@@ -104,6 +159,136 @@ pub use layers::{Builder, Format, Source};
 ///     sub_name: String,
 /// }
 /// ```
+///
+/// ## Field attributes
+///
+/// - `#[layered(subconfig)]`: the field is another `LayeredConf` struct, merged field by field.
+///   An `Option` of one is `None` unless a Layer sets any of its fields, when its required
+///   fields have to be set. A `HashMap` or `BTreeMap` of them is merged key by key, and a
+///   `Vec` of them by its `merge` strategy, with each entry's defaults filled in and its
+///   fields' paths including its key or index, e.g. `upstreams.primary.url`
+/// - `#[layered(load_config)]`: the field is a path to a further config file to load
+/// - `#[layered(default)]`, `#[layered(default = "fn_path")]`: default the field if no Layer sets it
+/// - `#[layered(env = "VAR")]`: bind the field to an environment variable, see `Source::EnvironmentBindings`
+/// - `#[layered(merge = "replace" | "append" | "prepend" | "union")]`: how collections from
+///   different Layers combine. `replace` (the default) takes the highest Layer's value,
+///   `append` adds higher Layers' items after lower ones, `prepend` before them, and `union`
+///   adds missing entries from lower Layers, keeping the highest Layer's value for map keys.
+///   The defaults count as the lowest Layer. Only collections and `Vec`s of subconfigs take a
+///   strategy, as single subconfigs are merged field by field and maps of them key by key.
+/// - `#[layered(range(min = 1, max = 65535))]`: the value must be within the bounds, either of
+///   which can be left out. Bounds that aren't literals, e.g. negative numbers, are given as
+///   strings: `min = "-1"`
+/// - `#[layered(non_empty)]`: the string, path or collection must not be empty
+/// - `#[layered(regex = "pattern")]`: the string must match the pattern, with the `regex` feature
+/// - `#[layered(path_exists)]`: the path must exist
+/// - `#[layered(validate = "fn_path")]`: `fn_path(&value)` must return `Ok(())`, or an error
+///   that's displayed as the failure
+///
+/// The validation attributes are checked at solidify time, only checking `Option`s that are
+/// set. Every failure is reported together in `Error::ValidationFailed`.
+///
+/// ## Struct attributes
+///
+/// - `#[layered(subconfig)]`: the struct is used as a subconfig
+/// - `#[layered(default)]`: default every field from the struct's `Default` impl
+/// - `#[layered(unset_flag)]`: add an `--unset FIELD` flag to the root Config's arguments,
+///   see [Unsetting fields](#unsetting-fields)
+/// - `#[layered(validate_with = "fn_path")]`: check rules covering several fields. After
+///   solidifying, `fn_path(&config)` must return `Ok(())`, or an error that's displayed as the
+///   failure. It's run for the root Config and for every subconfig, after their field checks,
+///   and its failures are reported with theirs.
+///
+/// ```rust
+/// use layeredconf::LayeredConf;
+///
+/// #[derive(LayeredConf, serde::Deserialize)]
+/// #[layered(validate_with = "check_bounds")]
+/// struct Config {
+///     #[clap(long)]
+///     min: u32,
+///     #[clap(long)]
+///     max: u32,
+/// }
+///
+/// fn check_bounds(config: &Config) -> Result<(), String> {
+///     if config.min <= config.max {
+///         Ok(())
+///     } else {
+///         Err("min must not be more than max".to_string())
+///     }
+/// }
+/// ```
+///
+/// ## Unsetting fields
+///
+/// A Layer can unset fields set by lower Layers by listing them in `_unset`, or on the
+/// command line with `--unset` when the Config has `#[layered(unset_flag)]`. Unset fields are
+/// reset to their default, or to `None` for `Option`s without one. Paths into subconfigs are
+/// dotted, use the keys of fields as written in config files, after serde's renames, and are
+/// relative to the section they're listed in. Naming a subconfig unsets all of its fields,
+/// and naming an entry of a map of subconfigs, e.g. `upstreams.primary`, removes it. Paths
+/// that aren't fields are unknown keys, see [`Builder::strict`].
+///
+/// `_unset` is a reserved key in every section, isn't changed by `rename_all`, and no field
+/// can be named or renamed to it.
+///
+/// ```yaml
+/// _unset: [optional, db.uri]
+/// db:
+///   _unset: [pool_size]
+/// ```
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use layeredconf::LayeredConf;
+///
+/// #[derive(LayeredConf, serde::Deserialize)]
+/// struct Config {
+///     #[layered(merge = "append")]
+///     #[clap(long)]
+///     plugins: Vec<String>,
+///     #[layered(merge = "union")]
+///     #[clap(skip)]
+///     options: HashMap<String, String>,
+/// }
+/// ```
+///
+/// ## Enums
+///
+/// Enums with named or unit variants, internally tagged with `#[serde(tag = "...")]`, are
+/// configs too, usually as subconfigs. A Layer can set fields of the variant selected by a
+/// lower one without repeating its tag, and a Layer that selects another variant ignores
+/// the lower Layers that select a different one. Only the fields of the variant that's
+/// finally selected are required, and the tag is required. Fields with the same name in
+/// several variants are shared, so must have the same type. The enum must implement `Debug`,
+/// to diff values of different variants.
+///
+/// ```rust
+/// use layeredconf::LayeredConf;
+///
+/// #[derive(LayeredConf, serde::Deserialize, Debug)]
+/// #[layered(subconfig)]
+/// #[serde(tag = "type", rename_all = "snake_case")]
+/// enum Storage {
+///     S3 {
+///         #[clap(long)]
+///         bucket: String,
+///         #[layered(default = "default_region")]
+///         #[clap(long)]
+///         region: String,
+///     },
+///     Local {
+///         #[clap(long)]
+///         path: String,
+///     },
+/// }
+///
+/// fn default_region() -> String {
+///     "us-east-1".to_string()
+/// }
+/// ```
 pub use layeredconf_derive::LayeredConf;
 
 /// LayeredConf Result
@@ -113,10 +298,22 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(ThisError, Debug)]
 pub enum Error {
     /// Solidify failed with missing fields set
-    #[error("Solidify failed, missing fields {missing:?}")]
+    #[error("Solidify failed, missing fields {}", display_list(.missing))]
     SolidifyFailedMissing {
-        /// The missing fields
-        missing: Vec<String>,
+        /// The missing fields, from every subconfig
+        missing: Vec<MissingField>,
+    },
+    /// Solidify failed, values failed validation
+    #[error("Validation failed, {}", display_list(.failures))]
+    ValidationFailed {
+        /// Every failure, from every subconfig
+        failures: Vec<ValidationFailure>,
+    },
+    /// A strict Layer has keys that aren't fields of the Config
+    #[error("Unknown keys {}", display_list(.unknown))]
+    UnknownKeys {
+        /// Every unknown key in the Layer
+        unknown: Vec<UnknownKey>,
     },
     /// Solidify failed, no layers
     #[error("Solidify failed, no layers")]
@@ -130,6 +327,14 @@ pub enum Error {
     /// File auto format detection failed
     #[error("Auto format detection failed")]
     AutoFormatFailed,
+    /// File auto format detection found more than one matching format
+    #[error("Auto format detection is ambiguous between {candidates:?} for {path:?}")]
+    AutoFormatAmbiguous {
+        /// Path that failed, if loading a file
+        path: Option<PathBuf>,
+        /// The formats that matched
+        candidates: Vec<Format>,
+    },
     /// A file was loaded in two Layers
     #[error("Loop detected loading config files")]
     LoopingLoadConfig {
@@ -173,23 +378,125 @@ pub enum Error {
         path: PathBuf,
     },
     /// Json error
+    #[cfg(feature = "json")]
     #[error("Json Error {wrapped:?}")]
     JsonError {
         /// Wrapped error
         wrapped: serde_json::Error,
     },
     /// Toml error
+    #[cfg(feature = "toml")]
     #[error("Toml Error {wrapped:?}")]
     TomlError {
         /// Wrapped error
         wrapped: toml::de::Error,
     },
+    /// Toml serialization error
+    #[cfg(feature = "toml")]
+    #[error("Toml Error {wrapped:?}")]
+    TomlSerializeError {
+        /// Wrapped error
+        wrapped: toml::ser::Error,
+    },
     /// Yaml error
+    #[cfg(feature = "yaml")]
     #[error("Yaml Error {wrapped:?}")]
     YamlError {
         /// Wrapped error
         wrapped: serde_yaml::Error,
     },
+    /// Error from a registered `FormatBackend`
+    #[error("{format} Error {wrapped:?}")]
+    FormatError {
+        /// Name of the format
+        format: String,
+        /// Wrapped error
+        wrapped: formats::BoxError,
+    },
+    /// Config can't be dumped in this format
+    #[error("Can't dump config as {format:?}")]
+    DumpUnsupported {
+        /// The format that failed
+        format: Format,
+    },
+    /// No `FormatBackend` registered with this name
+    #[error("Unknown format {name:?}")]
+    UnknownFormat {
+        /// The format name that failed
+        name: String,
+    },
+    /// Error watching config files
+    #[cfg(feature = "watch")]
+    #[error("Watch Error {wrapped:?}")]
+    WatchError {
+        /// Wrapped error
+        wrapped: notify::Error,
+    },
+    /// Error registering a signal handler
+    #[cfg(all(feature = "signal", unix))]
+    #[error("Signal Error {wrapped:?}")]
+    SignalError {
+        /// Wrapped io::Error
+        wrapped: std::io::Error,
+    },
+    /// A Config's defaults couldn't be serialized, for its schema or template
+    #[cfg(any(feature = "schema", feature = "template"))]
+    #[error("Serializing defaults failed {wrapped:?}")]
+    DefaultsError {
+        /// Wrapped error
+        wrapped: serde_json::Error,
+    },
+    /// A Source failed to parse, with where
+    ///
+    /// Wraps the parse error of a file or string Source, e.g. a `JsonError`.
+    #[error("{source} at {location}")]
+    At {
+        /// Where the error is
+        location: Box<Location>,
+        /// The parse error
+        source: Box<Error>,
+    },
+    /// Environment variable error
+    #[error("Environment Error {wrapped:?}")]
+    EnvironmentError {
+        /// Wrapped error
+        wrapped: serde::de::value::Error,
+    },
+}
+
+/// A required field that no Layer set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingField {
+    /// Dotted path of the field by its keys in config files, e.g. `db.uri`
+    pub path: String,
+    /// Command line flag that sets the field, e.g. `--uri`
+    pub flag: Option<String>,
+}
+
+impl std::fmt::Display for MissingField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.flag {
+            Some(flag) => write!(f, "{} ({})", self.path, flag),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+fn display_list<T: std::fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(T::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[doc(hidden)]
+pub fn field_path(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
 }
 
 pub(crate) fn map_io_error(path: &'_ Path) -> impl Fn(std::io::Error) -> Error + '_ {
@@ -212,24 +519,47 @@ pub(crate) fn map_canonicalization_error(path: &'_ Path) -> impl Fn(std::io::Err
     }
 }
 
+#[cfg(feature = "json")]
 impl From<serde_json::Error> for Error {
     fn from(wrapped: serde_json::Error) -> Self {
         Error::JsonError { wrapped }
     }
 }
 
+#[cfg(feature = "toml")]
 impl From<toml::de::Error> for Error {
     fn from(wrapped: toml::de::Error) -> Self {
         Error::TomlError { wrapped }
     }
 }
 
+#[cfg(feature = "toml")]
+impl From<toml::ser::Error> for Error {
+    fn from(wrapped: toml::ser::Error) -> Self {
+        Error::TomlSerializeError { wrapped }
+    }
+}
+
+#[cfg(feature = "yaml")]
 impl From<serde_yaml::Error> for Error {
     fn from(wrapped: serde_yaml::Error) -> Self {
         Error::YamlError { wrapped }
     }
 }
 
+#[cfg(feature = "watch")]
+impl From<notify::Error> for Error {
+    fn from(wrapped: notify::Error) -> Self {
+        Error::WatchError { wrapped }
+    }
+}
+
+impl From<serde::de::value::Error> for Error {
+    fn from(wrapped: serde::de::value::Error) -> Self {
+        Error::EnvironmentError { wrapped }
+    }
+}
+
 #[doc(hidden)]
 pub trait LayeredConfSolid {
     type Layer: LayeredConfLayer + Default + serde::de::DeserializeOwned;
@@ -242,14 +572,202 @@ pub trait LayeredConfLayer {
     fn load_configs(&self) -> Vec<PathBuf>;
 
     fn default_layer() -> Self;
+
+    /// The defaults, with those of optional subconfigs filled in as if they were enabled,
+    /// for templates and schemas to show
+    fn template_defaults() -> Self;
+
+    fn env_layer(vars: &HashMap<String, String>) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Returns true if no fields are set or unset
+    fn empty(&self) -> bool;
+
+    /// Returns true if a Layer had this section, even if it set none of its fields
+    fn present(&self) -> bool;
+
+    /// Fills in the defaults of the optional subconfigs that are enabled, which the defaults
+    /// of a Config's Layer leave out
+    fn fill_optional_defaults(&mut self);
+
+    fn set_fields(&self) -> Vec<String>;
+
+    /// The variants of the enums this Layer selects, by the key paths of their tags, e.g.
+    /// `storage.type`
+    fn variants(&self) -> Vec<(String, &'static str)>;
+
+    /// Paths this Layer unsets in the Layers below it, including within its subconfigs, as
+    /// the keys of fields
+    fn unset_fields(&self) -> Vec<String>;
+
+    fn meta() -> meta::StructMeta
+    where
+        Self: Sized;
+}
+
+/// The Layer of an optional subconfig with its defaults filled in, or `None` if no Layer
+/// had its section or set any of its fields
+#[doc(hidden)]
+pub fn optional_subconfig<TLayer>(layer: &TLayer) -> Option<TLayer>
+where
+    TLayer: LayeredConfLayer + LayeredConfMerge<TLayer> + Clone,
+{
+    if absent_subconfig(layer) {
+        return None;
+    }
+    Some(with_defaults(layer))
+}
+
+/// Returns true if no Layer had an optional subconfig's section or set any of its fields
+#[doc(hidden)]
+pub fn absent_subconfig<TLayer: LayeredConfLayer>(layer: &TLayer) -> bool {
+    layer.empty() && !layer.present()
+}
+
+/// The Layer of a subconfig in a collection with its defaults filled in, as the defaults
+/// of a Config's Layer don't include its collections' entries
+#[doc(hidden)]
+pub fn with_defaults<TLayer>(layer: &TLayer) -> TLayer
+where
+    TLayer: LayeredConfLayer + LayeredConfMerge<TLayer> + Clone,
+{
+    let mut layer = layer.clone();
+    layer.merge_from(&TLayer::default_layer());
+    layer
+}
+
+/// Adds the required fields of an entry of a collection of subconfigs that aren't set, which
+/// have no command line flags, as collections are skipped by clap
+#[doc(hidden)]
+pub fn entry_missing_into<TSolid, TLayer>(
+    layer: &TLayer,
+    prefix: &str,
+    missing: &mut Vec<MissingField>,
+) where
+    TLayer: LayeredConfSolidify<TSolid> + LayeredConfLayer + LayeredConfMerge<TLayer> + Clone,
+{
+    let mut entry_missing = vec![];
+    with_defaults(layer).missing_into(prefix, &mut entry_missing);
+    missing.extend(entry_missing.into_iter().map(|field| MissingField {
+        flag: None,
+        ..field
+    }));
+}
+
+#[doc(hidden)]
+pub fn from_env_var<T>(var: &str, value: &str) -> Result<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    Ok(env::from_var(var, value)?)
+}
+
+/// Command line parsing for a Layer, when the `clap` feature is enabled
+#[doc(hidden)]
+#[cfg(feature = "clap")]
+pub trait LayeredConfArgs: clap::Parser {}
+
+#[cfg(feature = "clap")]
+impl<T> LayeredConfArgs for T where T: clap::Parser {}
+
+/// Command line parsing for a Layer, when the `clap` feature is enabled
+#[doc(hidden)]
+#[cfg(not(feature = "clap"))]
+pub trait LayeredConfArgs {}
+
+#[cfg(not(feature = "clap"))]
+impl<T> LayeredConfArgs for T {}
+
+/// Command line parsing for a subconfig's Layer, when the `clap` feature is enabled
+#[doc(hidden)]
+#[cfg(feature = "clap")]
+pub trait LayeredConfSubArgs: clap::Args {}
+
+#[cfg(feature = "clap")]
+impl<T> LayeredConfSubArgs for T where T: clap::Args {}
+
+/// Command line parsing for a subconfig's Layer, when the `clap` feature is enabled
+#[doc(hidden)]
+#[cfg(not(feature = "clap"))]
+pub trait LayeredConfSubArgs {}
+
+#[cfg(not(feature = "clap"))]
+impl<T> LayeredConfSubArgs for T {}
+
+/// What the derive needs of the Layer of a subconfig whose type is a type parameter
+#[doc(hidden)]
+pub trait LayeredConfSubLayer<TSolid>:
+    LayeredConfLayer
+    + LayeredConfMerge<Self>
+    + LayeredConfSolidify<TSolid>
+    + LayeredConfDiff
+    + LayeredConfSubArgs
+    + Default
+    + Clone
+    + std::fmt::Debug
+    + serde::Serialize
+{
+}
+
+impl<T, TSolid> LayeredConfSubLayer<TSolid> for T where
+    T: LayeredConfLayer
+        + LayeredConfMerge<T>
+        + LayeredConfSolidify<TSolid>
+        + LayeredConfDiff
+        + LayeredConfSubArgs
+        + Default
+        + Clone
+        + std::fmt::Debug
+        + serde::Serialize
+{
 }
 
 #[doc(hidden)]
 pub trait LayeredConfMerge<TLayer> {
     fn merge_from(&mut self, other: &TLayer);
+
+    /// Passes the paths unset by a parent Layer within its subconfig `field` to this Layer
+    fn push_unset(&mut self, unset: &[String], field: &str);
+
+    fn clear_unset(&mut self);
+
+    /// The path of the value merged as a whole that the set field `path` is within, and
+    /// whether Layers' values of it accumulate, rather than the highest replacing the rest
+    fn merge_unit(path: &str) -> Option<(String, bool)>
+    where
+        Self: Sized;
 }
 
 #[doc(hidden)]
 pub trait LayeredConfSolidify<TSolid> {
-    fn solidify(&self) -> Result<TSolid>;
+    fn solidify(&self) -> Result<TSolid> {
+        let mut missing = vec![];
+        self.missing_into("", &mut missing);
+        if !missing.is_empty() {
+            return Err(Error::SolidifyFailedMissing { missing });
+        }
+
+        let solid = self.solidify_unchecked();
+
+        let mut failures = vec![];
+        Self::validate_into(&solid, "", &mut failures);
+        if !failures.is_empty() {
+            return Err(Error::ValidationFailed { failures });
+        }
+
+        Ok(solid)
+    }
+
+    /// Adds the required fields that aren't set, with paths under `prefix`
+    fn missing_into(&self, prefix: &str, missing: &mut Vec<MissingField>);
+
+    /// Builds the Config, panicking if a required field isn't set
+    ///
+    /// Only for `solidify` and subconfigs' Layers, once `missing_into` found nothing missing.
+    #[doc(hidden)]
+    fn solidify_unchecked(&self) -> TSolid;
+
+    /// Adds the values of `solid` that fail validation, with paths under `prefix`
+    fn validate_into(solid: &TSolid, prefix: &str, failures: &mut Vec<ValidationFailure>);
 }
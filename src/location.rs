@@ -0,0 +1,529 @@
+//! Locations of values and errors within config Sources
+
+use std::{fmt, ops::Range, sync::Arc};
+
+use crate::{Error, Format, Source};
+
+/// Where a value or error is within a config Source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The Source, with the canonical path for files
+    pub origin: Source,
+    /// Line, counting from 1, if known, which it isn't for values set in custom formats
+    pub line: Option<usize>,
+    /// Column in chars, counting from 1, if known
+    pub column: Option<usize>,
+    /// Byte range of the offending key or value within `text`, if known
+    pub span: Option<Range<usize>>,
+    /// Text of the file or string the Source was loaded from
+    pub text: Option<Arc<str>>,
+}
+
+impl Location {
+    /// A location at a line and column, spanning the token there
+    pub(crate) fn new(
+        origin: Source,
+        text: Option<Arc<str>>,
+        line_col: Option<(usize, usize)>,
+    ) -> Self {
+        let span = text
+            .as_deref()
+            .zip(line_col)
+            .and_then(|(text, (line, column))| token_at(text, line, column));
+        Self {
+            origin,
+            line: line_col.map(|(line, _)| line),
+            column: line_col.map(|(_, column)| column),
+            span,
+            text,
+        }
+    }
+
+    /// A location spanning `span` of `text`
+    pub(crate) fn spanning(origin: Source, text: Arc<str>, span: Range<usize>) -> Self {
+        let before = &text[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Self {
+            origin,
+            line: Some(before.matches('\n').count() + 1),
+            column: Some(before[line_start..].chars().count() + 1),
+            span: Some(span),
+            text: Some(text),
+        }
+    }
+
+    /// Path of the file, or a description of the Source
+    pub(crate) fn origin_name(&self) -> String {
+        match &self.origin {
+            Source::File(path, _) | Source::FileOptional(path, _) => path.display().to_string(),
+            Source::String(..) => "<string>".to_string(),
+            Source::Environment(_)
+            | Source::EnvironmentFrom(..)
+            | Source::EnvironmentBindings
+            | Source::EnvironmentBindingsFrom(_) => "<environment>".to_string(),
+            #[cfg(feature = "clap")]
+            Source::Arguments | Source::ArgumentsFrom(_) => "<arguments>".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.origin_name())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a location as a suffix for error messages
+pub(crate) fn at(location: Option<&Location>) -> String {
+    match location {
+        Some(location) => format!(" at {}", location),
+        None => String::new(),
+    }
+}
+
+impl Error {
+    /// Wraps a parse error in [`Error::At`] with the Source that failed to parse, and the
+    /// line and column of the error when known
+    pub(crate) fn with_origin(self, origin: &Source, text: Option<&Arc<str>>) -> Self {
+        let line_col = match &self {
+            #[cfg(feature = "json")]
+            Error::JsonError { wrapped } => Some((wrapped.line(), wrapped.column()))
+                .filter(|(line, _)| *line > 0)
+                .map(|(line, column)| char_column(text, line, column)),
+            #[cfg(feature = "toml")]
+            Error::TomlError { wrapped } => wrapped
+                .line_col()
+                .map(|(line, column)| char_column(text, line + 1, column + 1)),
+            #[cfg(feature = "yaml")]
+            Error::YamlError { wrapped } => wrapped
+                .location()
+                .map(|location| (location.line(), location.column())),
+            Error::FormatError { .. } => None,
+            _ => return self,
+        };
+        Error::At {
+            location: Box::new(Location::new(origin.clone(), text.cloned(), line_col)),
+            source: Box::new(self),
+        }
+    }
+}
+
+/// Finds the byte range of the token starting at a line and column, counting from 1
+fn token_at(text: &str, line: usize, column: usize) -> Option<Range<usize>> {
+    let start = offset(text, line, column)?;
+    let rest = &text[start..];
+    let len = rest
+        .find(|c: char| c.is_whitespace() || ",:;=[]{}".contains(c))
+        .unwrap_or(rest.len());
+    let first = rest.chars().next().map_or(0, char::len_utf8);
+    Some(start..start + len.max(first))
+}
+
+/// The byte offset of a line and column, counting from 1, with columns in chars as TOML
+/// and YAML count them
+fn offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = line_offset(text, line)?;
+    let line_text = text[line_start..].split('\n').next().unwrap_or_default();
+    let offset = line_text
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(line_text.len(), |(offset, _)| offset);
+    Some(line_start + offset)
+}
+
+/// A line and column with the column in bytes, as JSON's and TOML's parsers count them, with
+/// the column in chars instead, as locations count them
+#[cfg(any(feature = "json", feature = "toml"))]
+fn char_column(text: Option<&Arc<str>>, line: usize, column: usize) -> (usize, usize) {
+    let chars = text.and_then(|text| {
+        let start = line_offset(text, line)?;
+        Some(
+            text[start..byte_offset(text, line, column)?]
+                .chars()
+                .count()
+                + 1,
+        )
+    });
+    (line, chars.unwrap_or(column))
+}
+
+/// The byte offset of a line and column, counting from 1, with columns in bytes as JSON
+/// and TOML count them
+#[cfg(any(feature = "json", feature = "toml"))]
+fn byte_offset(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = line_offset(text, line)?;
+    let line_len = text[line_start..]
+        .find('\n')
+        .unwrap_or(text.len() - line_start);
+    let mut offset = line_start + column.saturating_sub(1).min(line_len);
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    Some(offset)
+}
+
+/// The byte offset of the start of a line, counting from 1
+fn line_offset(text: &str, line: usize) -> Option<usize> {
+    match line {
+        0 | 1 => Some(0),
+        line => Some(text.match_indices('\n').nth(line - 2)?.0 + 1),
+    }
+}
+
+/// Finds the byte range of the key that sets the value at a dotted field path in `text`,
+/// or of the entry a path ending in a list index names
+///
+/// JSON, TOML and YAML are parsed again to find the key where their parsers read it.
+/// Custom formats aren't located.
+#[cfg_attr(
+    not(any(feature = "json", feature = "toml", feature = "yaml")),
+    allow(unused_variables)
+)]
+pub(crate) fn locate(text: &str, format: Format, path: &str) -> Option<Range<usize>> {
+    match format {
+        #[cfg(feature = "json")]
+        Format::Json => parsed::json(text, path),
+        #[cfg(feature = "toml")]
+        Format::Toml => parsed::toml(text, path),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => parsed::yaml(text, path),
+        _ => None,
+    }
+}
+
+/// Locates keys by walking down their path while the format's parser deserializes the text
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+mod parsed {
+    use std::{fmt, ops::Range};
+
+    use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+    #[cfg(feature = "json")]
+    pub(super) fn json(text: &str, path: &str) -> Option<Range<usize>> {
+        let path = path.split('.').collect::<Vec<_>>();
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        let error = Walk::new(&path, Fail)
+            .deserialize(&mut deserializer)
+            .err()?;
+        if !error.to_string().contains(FOUND) {
+            return None;
+        }
+        // Keys are reported at their closing quote, and entries where they end
+        let end = super::byte_offset(text, error.line(), error.column())?;
+        match key(&path) {
+            Some(key) => unquoted(text, end.checked_sub(key.len())?, key),
+            None => token_before(text, end),
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    pub(super) fn toml(text: &str, path: &str) -> Option<Range<usize>> {
+        let path = path.split('.').collect::<Vec<_>>();
+        let mut deserializer = toml::Deserializer::new(text);
+        let span = Walk::new(&path, Span)
+            .deserialize(&mut deserializer)
+            .ok()??;
+        match key(&path) {
+            Some(key) => unquoted(text, span.start, key),
+            None => Some(span),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    pub(super) fn yaml(text: &str, path: &str) -> Option<Range<usize>> {
+        let path = path.split('.').collect::<Vec<_>>();
+        let deserializer = serde_yaml::Deserializer::from_str(text);
+        let error = Walk::new(&path, Fail).deserialize(deserializer).err()?;
+        if !error.to_string().contains(FOUND) {
+            return None;
+        }
+        let location = error.location()?;
+        match key(&path) {
+            Some(key) => unquoted(
+                text,
+                super::offset(text, location.line(), location.column())?,
+                key,
+            ),
+            None => super::token_at(text, location.line(), location.column()),
+        }
+    }
+
+    /// The key the path ends at, or `None` if it ends at a list index
+    fn key<'a>(path: &[&'a str]) -> Option<&'a str> {
+        path.last()
+            .copied()
+            .filter(|key| key.parse::<usize>().is_err())
+    }
+
+    /// The range of `key` at `start`, or within the quotes there
+    fn unquoted(text: &str, start: usize, key: &str) -> Option<Range<usize>> {
+        let start = match text[start..].strip_prefix(['"', '\'']) {
+            Some(_) => start + 1,
+            None => start,
+        };
+        text[start..]
+            .starts_with(key)
+            .then_some(start..start + key.len())
+    }
+
+    /// The range of the token ending at `end`, for JSON's positions
+    #[cfg(feature = "json")]
+    fn token_before(text: &str, end: usize) -> Option<Range<usize>> {
+        let end = end + text[end..].chars().next().map_or(0, char::len_utf8);
+        let start = text[..end]
+            .rfind(|c: char| c.is_whitespace() || ",:[{".contains(c))
+            .map_or(0, |index| index + 1);
+        (start < end).then_some(start..end)
+    }
+
+    /// The message of the error a Walk fails with once it reaches the key, so that the
+    /// parser adds where it is
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    const FOUND: &str = "layeredconf: found key";
+
+    /// How a Walk reads keys, and the entry a path ending in a list index names
+    trait Reached: Copy {
+        /// Reads a map key, returning whether it's `target`, and its span if it's the last
+        /// key of the path
+        fn key<'de, D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+            target: &str,
+            last: bool,
+        ) -> Result<Step, D::Error>;
+
+        /// Reads the entry, returning its span
+        fn entry<'de, D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Option<Range<usize>>, D::Error>;
+    }
+
+    /// What a Walk found at a map key
+    enum Step {
+        /// A key that's not on the path
+        Other,
+        /// The key on the path, with its span if it's the last and that's known
+        Target(Option<Range<usize>>),
+    }
+
+    /// Fails with [`FOUND`] at the last key, for parsers that only give positions in their
+    /// errors
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    #[derive(Clone, Copy)]
+    struct Fail;
+
+    #[cfg(any(feature = "json", feature = "yaml"))]
+    impl Reached for Fail {
+        fn key<'de, D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+            target: &str,
+            last: bool,
+        ) -> Result<Step, D::Error> {
+            struct KeyVisitor<'a>(&'a str, bool);
+
+            impl<'de> Visitor<'de> for KeyVisitor<'_> {
+                type Value = Step;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("a map key")
+                }
+
+                fn visit_str<E: serde::de::Error>(self, key: &str) -> Result<Step, E> {
+                    match (key == self.0, self.1) {
+                        (false, _) => Ok(Step::Other),
+                        (true, false) => Ok(Step::Target(None)),
+                        (true, true) => Err(E::custom(FOUND)),
+                    }
+                }
+            }
+
+            deserializer.deserialize_str(KeyVisitor(target, last))
+        }
+
+        fn entry<'de, D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Option<Range<usize>>, D::Error> {
+            // Expects nothing, so any value fails
+            struct EntryVisitor;
+
+            impl<'de> Visitor<'de> for EntryVisitor {
+                type Value = Option<Range<usize>>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str(FOUND)
+                }
+            }
+
+            deserializer.deserialize_any(EntryVisitor)
+        }
+    }
+
+    /// Reads the spans of keys and entries, for TOML
+    #[cfg(feature = "toml")]
+    #[derive(Clone, Copy)]
+    struct Span;
+
+    #[cfg(feature = "toml")]
+    impl Reached for Span {
+        fn key<'de, D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+            target: &str,
+            _last: bool,
+        ) -> Result<Step, D::Error> {
+            let key: toml::Spanned<String> = serde::Deserialize::deserialize(deserializer)?;
+            let (start, end) = key.span();
+            Ok(match key.get_ref() == target {
+                true => Step::Target(Some(start..end)),
+                false => Step::Other,
+            })
+        }
+
+        fn entry<'de, D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Option<Range<usize>>, D::Error> {
+            let entry: toml::Spanned<IgnoredAny> = serde::Deserialize::deserialize(deserializer)?;
+            let (start, end) = entry.span();
+            Ok(Some(start..end))
+        }
+    }
+
+    /// Deserializes any value, walking down the keys and list indexes of `path` within it
+    /// until it reaches the last
+    struct Walk<'a, R> {
+        path: &'a [&'a str],
+        reached: R,
+    }
+
+    impl<'a, R: Reached> Walk<'a, R> {
+        fn new(path: &'a [&'a str], reached: R) -> Self {
+            Self { path, reached }
+        }
+
+        fn next(&self) -> Self {
+            Self::new(&self.path[1..], self.reached)
+        }
+    }
+
+    impl<'de, R: Reached> DeserializeSeed<'de> for Walk<'_, R> {
+        type Value = Option<Range<usize>>;
+
+        fn deserialize<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+    }
+
+    /// Reads a map key for a Walk
+    struct KeySeed<'a, R> {
+        target: &'a str,
+        last: bool,
+        reached: R,
+    }
+
+    impl<'de, R: Reached> DeserializeSeed<'de> for KeySeed<'_, R> {
+        type Value = Step;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Step, D::Error> {
+            self.reached.key(deserializer, self.target, self.last)
+        }
+    }
+
+    /// Reads the entry a path ends at for a Walk
+    struct EntrySeed<R>(R);
+
+    impl<'de, R: Reached> DeserializeSeed<'de> for EntrySeed<R> {
+        type Value = Option<Range<usize>>;
+
+        fn deserialize<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            self.0.entry(deserializer)
+        }
+    }
+
+    impl<'de, R: Reached> Visitor<'de> for Walk<'_, R> {
+        type Value = Option<Range<usize>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map or list")
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+
+        fn visit_newtype_struct<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(self)
+        }
+
+        // The rest of a map or list is read too, as parsers check that it's well formed
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut found = None;
+            if let Some(target) = self.path.first() {
+                let last = self.path.len() == 1;
+                let seed = || KeySeed {
+                    target,
+                    last,
+                    reached: self.reached,
+                };
+                while let Some(step) = map.next_key_seed(seed())? {
+                    match step {
+                        Step::Other => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                        Step::Target(span) if last => {
+                            map.next_value::<IgnoredAny>()?;
+                            found = span;
+                            break;
+                        }
+                        Step::Target(_) => {
+                            found = map.next_value_seed(self.next())?;
+                            break;
+                        }
+                    }
+                }
+            }
+            while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+            Ok(found)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut found = None;
+            if let Some(index) = self.path.first().and_then(|index| index.parse().ok()) {
+                for _ in 0..index {
+                    if seq.next_element::<IgnoredAny>()?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                found = match self.path.len() {
+                    1 => seq.next_element_seed(EntrySeed(self.reached))?,
+                    _ => seq.next_element_seed(self.next())?,
+                }
+                .flatten();
+            }
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            Ok(found)
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Merge strategies for collection fields, used by `#[layered(merge = "...")]`,
+//! and handling of fields unset by a higher Layer
+
+use std::{
+    collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    meta::{StructMeta, ValueKind},
+    LayeredConfMerge,
+};
+
+/// The unset path of every field of a Layer, written by pushing down an unset subconfig
+#[doc(hidden)]
+pub const UNSET_ALL: &str = "*";
+
+/// Whether a deserialized Layer had its section, as it always did if it was deserialized
+#[doc(hidden)]
+pub fn present() -> bool {
+    true
+}
+
+/// Returns true if `field` was unset by a higher Layer
+pub fn is_unset(unset: &[String], field: &str) -> bool {
+    unset.iter().any(|path| path == field || path == UNSET_ALL)
+}
+
+/// Returns true if `path`, or the subconfig or entry it's within, was unset by a higher Layer
+pub(crate) fn is_unset_path(unset: &[String], path: &str) -> bool {
+    unset
+        .iter()
+        .any(|unset| match unset.strip_suffix(UNSET_ALL) {
+            Some(prefix) if prefix.is_empty() || prefix.ends_with('.') => path.starts_with(prefix),
+            _ => path
+                .strip_prefix(unset.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.')),
+        })
+}
+
+/// Returns true if `path` is `prefix`, or a field or entry within it
+pub(crate) fn within(path: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// The variants of enums selected by the Layers seen so far, highest first, by the key paths
+/// of their tags, e.g. `storage.type`
+#[derive(Debug, Default)]
+pub(crate) struct Variants(BTreeMap<String, &'static str>);
+
+impl Variants {
+    /// Adds the variants a Layer selects, below Layers unsetting `unset`, returning the key
+    /// paths of the enums it selects another variant of than a higher Layer
+    ///
+    /// Those enums of the Layer aren't merged, as a higher Layer switched their variant.
+    pub(crate) fn select(
+        &mut self,
+        selected: Vec<(String, &'static str)>,
+        unset: &[String],
+    ) -> Vec<String> {
+        let mut switched: Vec<String> = vec![];
+        for (tag_path, variant) in selected {
+            if is_unset_path(unset, &tag_path)
+                || switched.iter().any(|path| within(&tag_path, path))
+            {
+                continue;
+            }
+            match self.0.entry(tag_path) {
+                btree_map::Entry::Vacant(entry) => {
+                    entry.insert(variant);
+                }
+                btree_map::Entry::Occupied(entry) => {
+                    if *entry.get() != variant {
+                        let enum_path = entry.key().rsplit_once('.').map_or("", |(path, _)| path);
+                        switched.push(enum_path.to_string());
+                    }
+                }
+            }
+        }
+        switched
+    }
+
+    /// The variant selected of the enum at `prefix`, a key path, if it's an enum
+    pub(crate) fn selected(&self, meta: &StructMeta, prefix: &str) -> Option<&'static str> {
+        let variants = meta.variants.as_ref()?;
+        self.0
+            .get(&crate::field_path(prefix, variants.tag))
+            .copied()
+    }
+
+    /// Returns true if `path`, a key path, is a field of an enum that its selected variant
+    /// doesn't have
+    pub(crate) fn excludes(&self, meta: &StructMeta, path: &str) -> bool {
+        let mut meta = Some(meta.clone());
+        let mut prefix = String::new();
+        let mut segments = path.split('.');
+        while let (Some(current), Some(segment)) = (meta.take(), segments.next()) {
+            if current.variants.is_some()
+                && !current
+                    .keys(self.selected(&current, &prefix))
+                    .contains(&segment)
+            {
+                return true;
+            }
+            prefix = crate::field_path(&prefix, segment);
+            let field = current.fields.iter().find(|field| field.key == segment);
+            meta = match field.map(|field| field.kind.clone()) {
+                Some(ValueKind::Subconfig(sub_meta)) => Some(sub_meta()),
+                Some(ValueKind::Array(kind) | ValueKind::Map(kind)) => {
+                    match (*kind, segments.next()) {
+                        (ValueKind::Subconfig(sub_meta), Some(key)) => {
+                            prefix = crate::field_path(&prefix, key);
+                            Some(sub_meta())
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+        }
+        false
+    }
+}
+
+/// The merge unit of `path` within a subconfig with Layer `L`, whose field path is the
+/// first `depth` segments of `path`
+#[doc(hidden)]
+pub fn sub_unit<L>(path: &str, depth: usize) -> Option<(String, bool)>
+where
+    L: LayeredConfMerge<L>,
+{
+    let (prefix_len, _) = path.match_indices('.').nth(depth - 1)?;
+    let (unit, accumulates) = L::merge_unit(&path[prefix_len + 1..])?;
+    Some((format!("{}.{}", &path[..prefix_len], unit), accumulates))
+}
+
+/// Passes the unset paths within the subconfig `field` down to its Layer
+///
+/// Unsetting the subconfig itself unsets all of its fields.
+pub fn push_unset(unset: &[String], field: &str, sub_unset: &mut Vec<String>) {
+    for path in unset {
+        let sub_path = if path == field || path == UNSET_ALL {
+            UNSET_ALL
+        } else {
+            match path
+                .strip_prefix(field)
+                .and_then(|rest| rest.strip_prefix('.'))
+            {
+                Some(sub_path) => sub_path,
+                None => continue,
+            }
+        };
+        if !sub_unset.iter().any(|existing| existing == sub_path) {
+            sub_unset.push(sub_path.to_string());
+        }
+    }
+}
+
+/// Adds the paths unset by a lower Layer, so they apply to the Layers below it
+pub fn extend_unset(unset: &mut Vec<String>, lower: &[String]) {
+    for path in lower {
+        if !unset.contains(path) {
+            unset.push(path.clone());
+        }
+    }
+}
+
+/// Appends the items of `higher` after the items of `lower`
+pub fn append<T>(higher: &mut Option<T>, lower: &Option<T>)
+where
+    T: Clone + Default + IntoIterator + Extend<<T as IntoIterator>::Item>,
+{
+    match (higher, lower) {
+        (_, None) => {}
+        (higher @ None, lower) => *higher = lower.clone(),
+        (Some(higher), Some(lower)) => {
+            let mut merged = lower.clone();
+            merged.extend(std::mem::take(higher));
+            *higher = merged;
+        }
+    }
+}
+
+/// Appends the items of `lower` after the items of `higher`
+pub fn prepend<T>(higher: &mut Option<T>, lower: &Option<T>)
+where
+    T: Clone + IntoIterator + Extend<<T as IntoIterator>::Item>,
+{
+    match (higher, lower) {
+        (_, None) => {}
+        (higher @ None, lower) => *higher = lower.clone(),
+        (Some(higher), Some(lower)) => higher.extend(lower.clone()),
+    }
+}
+
+/// Merges the entries of `lower` into `higher`, keeping `higher`'s on conflict
+pub fn union<T>(higher: &mut Option<T>, lower: &Option<T>)
+where
+    T: Union + Clone,
+{
+    match (higher, lower) {
+        (_, None) => {}
+        (higher @ None, lower) => *higher = lower.clone(),
+        (Some(higher), Some(lower)) => higher.union_from(lower),
+    }
+}
+
+/// Collections that can be merged with `#[layered(merge = "union")]`
+pub trait Union {
+    /// Adds the entries of `lower` that aren't already in `self`
+    fn union_from(&mut self, lower: &Self);
+}
+
+impl<T> Union for Vec<T>
+where
+    T: Clone + PartialEq,
+{
+    fn union_from(&mut self, lower: &Self) {
+        let mut merged = lower.clone();
+        for item in std::mem::take(self) {
+            if !merged.contains(&item) {
+                merged.push(item);
+            }
+        }
+        *self = merged;
+    }
+}
+
+impl<K, V, S> Union for HashMap<K, V, S>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+    S: BuildHasher,
+{
+    fn union_from(&mut self, lower: &Self) {
+        for (key, value) in lower {
+            self.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+impl<K, V> Union for BTreeMap<K, V>
+where
+    K: Clone + Ord,
+    V: Clone,
+{
+    fn union_from(&mut self, lower: &Self) {
+        for (key, value) in lower {
+            self.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+impl<T, S> Union for HashSet<T, S>
+where
+    T: Clone + Eq + Hash,
+    S: BuildHasher,
+{
+    fn union_from(&mut self, lower: &Self) {
+        self.extend(lower.iter().cloned());
+    }
+}
+
+impl<T> Union for BTreeSet<T>
+where
+    T: Clone + Ord,
+{
+    fn union_from(&mut self, lower: &Self) {
+        self.extend(lower.iter().cloned());
+    }
+}
+
+/// Merges the entries of a lower Layer's map of subconfigs into `higher` key by key,
+/// merging the Layers of entries with the same key field by field
+///
+/// Unsetting `field.key` drops the lower Layers' entry, and unsetting `field.key.path`
+/// unsets `path` within it.
+#[doc(hidden)]
+pub fn subconfig_map<M, L>(higher: &mut Option<M>, lower: &Option<M>, unset: &[String], field: &str)
+where
+    M: LayerMap<L> + Default,
+    L: LayeredConfMerge<L>,
+{
+    let lower = match lower {
+        Some(lower) => lower,
+        None => return,
+    };
+    let higher = higher.get_or_insert_with(M::default);
+    for (key, layer) in lower.entries() {
+        let path = crate::field_path(field, &key.to_string());
+        if is_unset(unset, &path) {
+            continue;
+        }
+        let entry = higher.entry_or_default(key);
+        entry.push_unset(unset, &path);
+        entry.merge_from(layer);
+    }
+}
+
+/// Maps whose values are subconfig Layers
+#[doc(hidden)]
+pub trait LayerMap<L> {
+    /// The map's key, which names the entry in field paths
+    type Key: Display;
+
+    /// The entries of the map
+    fn entries(&self) -> Vec<(&Self::Key, &L)>;
+
+    /// The Layer of the entry with `key`, inserting an empty one if there isn't one
+    fn entry_or_default(&mut self, key: &Self::Key) -> &mut L;
+}
+
+impl<K, L, S> LayerMap<L> for HashMap<K, L, S>
+where
+    K: Clone + Display + Eq + Hash,
+    L: Default,
+    S: BuildHasher,
+{
+    type Key = K;
+
+    fn entries(&self) -> Vec<(&K, &L)> {
+        self.iter().collect()
+    }
+
+    fn entry_or_default(&mut self, key: &K) -> &mut L {
+        self.entry(key.clone()).or_default()
+    }
+}
+
+impl<K, L> LayerMap<L> for BTreeMap<K, L>
+where
+    K: Clone + Display + Ord,
+    L: Default,
+{
+    type Key = K;
+
+    fn entries(&self) -> Vec<(&K, &L)> {
+        self.iter().collect()
+    }
+
+    fn entry_or_default(&mut self, key: &K) -> &mut L {
+        self.entry(key.clone()).or_default()
+    }
+}
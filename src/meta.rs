@@ -0,0 +1,114 @@
+//! Descriptions of Config structs, generated by the derive macro
+
+use crate::{LayeredConfLayer, LayeredConfSolid};
+
+/// A LayeredConf struct
+#[derive(Debug, Clone)]
+pub struct StructMeta {
+    /// Name of the Config struct
+    pub name: &'static str,
+    /// Doc comment of the struct
+    pub doc: Option<&'static str>,
+    /// The struct's fields, in declaration order
+    pub fields: Vec<FieldMeta>,
+    /// The tag and variants of an enum, `None` for structs
+    pub variants: Option<VariantsMeta>,
+}
+
+impl StructMeta {
+    /// The keys of the fields, or of an enum's tag and the fields of its variant `selected`
+    /// if one is
+    pub(crate) fn keys(&self, selected: Option<&str>) -> Vec<&'static str> {
+        let variant = self.variants.as_ref().and_then(|variants| {
+            let variant = variants
+                .variants
+                .iter()
+                .find(|v| Some(v.name) == selected)?;
+            Some((variants.tag, variant))
+        });
+        match variant {
+            Some((tag, variant)) => std::iter::once(tag)
+                .chain(variant.fields.iter().copied())
+                .collect(),
+            None => self
+                .fields
+                .iter()
+                .flat_map(|field| std::iter::once(field.key).chain(field.aliases.iter().copied()))
+                .collect(),
+        }
+    }
+}
+
+/// The variants of a LayeredConf enum
+#[derive(Debug, Clone)]
+pub struct VariantsMeta {
+    /// Key of the field naming the variant, from serde's `tag`
+    pub tag: &'static str,
+    /// The enum's variants, in declaration order
+    pub variants: Vec<VariantMeta>,
+}
+
+/// A variant of a LayeredConf enum
+#[derive(Debug, Clone)]
+pub struct VariantMeta {
+    /// Name of the variant
+    pub name: &'static str,
+    /// Keys of the variant's fields, and their serde `alias`es
+    pub fields: Vec<&'static str>,
+}
+
+/// A field of a LayeredConf struct
+#[derive(Debug, Clone)]
+pub struct FieldMeta {
+    /// Name of the field
+    pub name: &'static str,
+    /// Key of the field in config files, after serde's `rename` and `rename_all`
+    pub key: &'static str,
+    /// Other keys serde accepts for the field, from its `alias`es
+    pub aliases: Vec<&'static str>,
+    /// Whether serde's `flatten` puts the field's keys in the struct's
+    pub flatten: bool,
+    /// Doc comment of the field
+    pub doc: Option<&'static str>,
+    /// What values the field takes
+    pub kind: ValueKind,
+    /// Whether the field has to be set by a Layer, as it's neither an `Option` nor defaulted
+    pub required: bool,
+    /// Whether the field is an `Option`, so for a subconfig whether only a Layer setting
+    /// its section enables it
+    pub optional: bool,
+}
+
+impl FieldMeta {
+    /// Whether `key` names the field, by its key or one of its aliases
+    pub(crate) fn has_key(&self, key: &str) -> bool {
+        self.key == key || self.aliases.contains(&key)
+    }
+}
+
+/// The kind of value a field takes, worked out from its Rust type
+#[derive(Debug, Clone)]
+pub enum ValueKind {
+    /// Strings, chars and paths
+    String,
+    /// `bool`
+    Boolean,
+    /// Signed and unsigned integers
+    Integer,
+    /// Floating point numbers
+    Number,
+    /// Sequences and sets
+    Array(Box<ValueKind>),
+    /// Maps with string keys
+    Map(Box<ValueKind>),
+    /// A `subconfig`
+    Subconfig(fn() -> StructMeta),
+    /// Any other type
+    Any,
+}
+
+/// The StructMeta of a subconfig, for [`ValueKind::Subconfig`]
+#[doc(hidden)]
+pub fn subconfig<T: LayeredConfSolid>() -> StructMeta {
+    T::Layer::meta()
+}
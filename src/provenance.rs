@@ -0,0 +1,150 @@
+//! Tracks which Source supplied each value of a Config
+
+use std::collections::{btree_map, BTreeMap};
+
+use crate::{merge, LayeredConfLayer, LayeredConfMerge, Source};
+
+/// Where a value in a solidified Config came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueSource {
+    /// Set by a Layer loaded from `source`
+    Layer {
+        /// The Source of the Layer
+        source: Source,
+        /// The Sources that loaded this one via `load_config`, closest first
+        parents: Vec<Source>,
+    },
+    /// Filled from the Config's defaults
+    Default,
+    /// Merged from the values of several Layers, and maybe the defaults, by a field's
+    /// `append`, `prepend` or `union` merge strategy, highest first
+    Merged(Vec<ValueSource>),
+}
+
+/// Map of dotted paths of fields by their keys (e.g. `db.uri`) to the source of their value
+///
+/// Fields that weren't set by any Layer, or by the defaults, have no entry. Fields unset by a
+/// higher Layer come from the Layers above it, or the defaults, as do the fields of an enum
+/// whose variant a higher Layer switched. Fields of variants that aren't selected have no
+/// entry. The entries of a `Vec` of subconfigs merged with `append` or `prepend` are
+/// attributed as a whole, e.g. to `listeners`, as their indexes depend on the merge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    fields: BTreeMap<String, ValueSource>,
+}
+
+impl Provenance {
+    /// Returns the source of the value at `path`
+    pub fn get(&self, path: &str) -> Option<&ValueSource> {
+        self.fields.get(path)
+    }
+
+    /// Iterates over field paths and their sources, ordered by path
+    pub fn iter(&self) -> btree_map::Iter<'_, String, ValueSource> {
+        self.fields.iter()
+    }
+
+    /// Adds `source` to the sources of the accumulated value at `path`
+    fn push(&mut self, path: String, source: &ValueSource) {
+        match self.fields.entry(path) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(source.clone());
+            }
+            btree_map::Entry::Occupied(mut entry) => match entry.get_mut() {
+                ValueSource::Merged(sources) => {
+                    if !sources.contains(source) {
+                        sources.push(source.clone());
+                    }
+                }
+                existing => {
+                    if existing != source {
+                        *existing = ValueSource::Merged(vec![existing.clone(), source.clone()]);
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Builds a Provenance from Layers recorded highest first, following how they're merged
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    provenance: Provenance,
+    /// Paths unset by the Layers recorded so far
+    unset: Vec<String>,
+    /// Variants selected by the Layers recorded so far
+    variants: merge::Variants,
+    /// Values merged as a whole, and the index of the Layer whose value replaces the rest
+    claimed: BTreeMap<String, usize>,
+    layers: usize,
+}
+
+impl Recorder {
+    /// Records the fields `layer` sets that aren't unset or replaced by a higher Layer
+    pub(crate) fn record<L>(&mut self, layer: &L, source: ValueSource)
+    where
+        L: LayeredConfLayer + LayeredConfMerge<L>,
+    {
+        self.layers += 1;
+        let switched = self.variants.select(layer.variants(), &self.unset);
+        for path in layer.set_fields() {
+            if merge::is_unset_path(&self.unset, &path) {
+                continue;
+            }
+            let (unit, accumulates) = L::merge_unit(&path).unwrap_or_else(|| (path.clone(), false));
+            if accumulates {
+                self.provenance.push(unit, &source);
+            } else if !switched
+                .iter()
+                .any(|enum_path| merge::within(&path, enum_path))
+                && *self.claimed.entry(unit).or_insert(self.layers) == self.layers
+            {
+                self.provenance
+                    .fields
+                    .entry(path)
+                    .or_insert_with(|| source.clone());
+            }
+        }
+        merge::extend_unset(&mut self.unset, &layer.unset_fields());
+    }
+
+    /// Records the defaults, which are merged below all Layers whatever they unset, and those
+    /// filled into optional subconfigs and the entries of collections, the fields `filled`
+    /// sets that no Layer accounts for
+    ///
+    /// Fields of enums' variants that weren't selected are left out, as they're not solidified.
+    pub(crate) fn finish<L>(mut self, defaults: &L, filled: &L) -> Provenance
+    where
+        L: LayeredConfLayer + LayeredConfMerge<L>,
+    {
+        self.unset.clear();
+        self.record(defaults, ValueSource::Default);
+
+        for path in filled.set_fields() {
+            let recorded = self
+                .provenance
+                .fields
+                .keys()
+                .any(|recorded| merge::within(&path, recorded));
+            if !recorded {
+                self.provenance.fields.insert(path, ValueSource::Default);
+            }
+        }
+
+        let meta = L::meta();
+        let variants = &self.variants;
+        self.provenance
+            .fields
+            .retain(|path, _| !variants.excludes(&meta, path));
+        self.provenance
+    }
+}
+
+impl<'a> IntoIterator for &'a Provenance {
+    type Item = (&'a String, &'a ValueSource);
+    type IntoIter = btree_map::Iter<'a, String, ValueSource>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
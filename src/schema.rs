@@ -0,0 +1,121 @@
+//! JSON Schema generation for a Config's Layers
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    meta::{StructMeta, ValueKind},
+    Error, LayeredConfLayer, LayeredConfSolid, Result,
+};
+
+/// JSON Schema of a Config's Layers, for editor completion and validation of config files
+///
+/// Implemented for every `LayeredConf` Config, behind the `schema` feature. As any Layer
+/// may leave fields unset, no field is required by the schema. Doc comments become
+/// descriptions, defaults are included, and subconfigs are nested objects.
+///
+/// ```rust
+/// use layeredconf::{LayeredConf, LayeredConfSchema};
+///
+/// #[derive(LayeredConf, serde::Deserialize, serde::Serialize)]
+/// struct Config {
+///     /// Port to listen on
+///     #[layered(default = "default_port")]
+///     #[clap(long)]
+///     port: u16,
+/// }
+///
+/// fn default_port() -> u16 {
+///     8080
+/// }
+///
+/// let schema = Config::json_schema().unwrap();
+/// assert_eq!(schema["properties"]["port"]["type"], "integer");
+/// assert_eq!(schema["properties"]["port"]["default"], 8080);
+/// ```
+pub trait LayeredConfSchema {
+    /// Returns the JSON Schema (draft 7) of this Config's Layers
+    ///
+    /// Fails if the defaults can't be serialized as JSON, e.g. maps with non-string keys.
+    fn json_schema() -> Result<Value>;
+}
+
+impl<T> LayeredConfSchema for T
+where
+    T: LayeredConfSolid,
+    T::Layer: serde::Serialize,
+{
+    fn json_schema() -> Result<Value> {
+        let meta = T::Layer::meta();
+        let defaults = serde_json::to_value(T::Layer::template_defaults())
+            .map_err(|wrapped| Error::DefaultsError { wrapped })?;
+
+        let mut schema = Map::new();
+        schema.insert(
+            "$schema".to_string(),
+            json!("http://json-schema.org/draft-07/schema#"),
+        );
+        schema.insert("title".to_string(), json!(meta.name));
+        schema.extend(object_schema(&meta, &defaults));
+
+        Ok(Value::Object(schema))
+    }
+}
+
+fn object_schema(meta: &StructMeta, defaults: &Value) -> Map<String, Value> {
+    let mut properties = Map::new();
+    for field in &meta.fields {
+        let default = defaults.get(field.key).unwrap_or(&Value::Null);
+
+        let mut property = Map::new();
+        let doc = match (&field.kind, field.doc) {
+            (ValueKind::Subconfig(sub_meta), None) => sub_meta().doc,
+            (_, doc) => doc,
+        };
+        if let Some(doc) = doc {
+            property.insert("description".to_string(), json!(doc));
+        }
+        property.extend(kind_schema(&field.kind, default));
+        if !default.is_null() && !matches!(field.kind, ValueKind::Subconfig(_)) {
+            property.insert("default".to_string(), default.clone());
+        }
+
+        properties.insert(field.key.to_string(), Value::Object(property));
+    }
+
+    let mut schema = Map::new();
+    if let Some(doc) = meta.doc {
+        schema.insert("description".to_string(), json!(doc));
+    }
+    schema.insert("type".to_string(), json!("object"));
+    schema.insert("properties".to_string(), Value::Object(properties));
+    schema
+}
+
+fn kind_schema(kind: &ValueKind, default: &Value) -> Map<String, Value> {
+    let schema = match kind {
+        ValueKind::String => json!({ "type": "string" }),
+        ValueKind::Boolean => json!({ "type": "boolean" }),
+        ValueKind::Integer => json!({ "type": "integer" }),
+        ValueKind::Number => json!({ "type": "number" }),
+        ValueKind::Array(item) => json!({
+            "type": "array",
+            "items": kind_schema(item, &Value::Null),
+        }),
+        ValueKind::Map(value) => json!({
+            "type": "object",
+            "additionalProperties": kind_schema(value, &Value::Null),
+        }),
+        ValueKind::Subconfig(meta) => {
+            let mut schema = object_schema(&meta(), default);
+            // The field's own doc comment, if any, is used instead
+            schema.remove("description");
+            return schema;
+        }
+        ValueKind::Any => json!({}),
+    };
+
+    match schema {
+        Value::Object(schema) => schema,
+        _ => unreachable!(),
+    }
+}
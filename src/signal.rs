@@ -0,0 +1,117 @@
+//! Reloads a Config on SIGHUP
+
+use std::{sync::Arc, thread};
+
+use arc_swap::ArcSwap;
+use signal_hook::{consts::SIGHUP, iterator::Signals};
+
+use crate::{
+    Builder, Error, LayeredConfArgs, LayeredConfLayer, LayeredConfMerge, LayeredConfSolid,
+    LayeredConfSolidify, Result,
+};
+
+/// A Config that's reloaded on SIGHUP, returned by [`Builder::reload_on_sighup`]
+///
+/// The Config is published through an [`ArcSwap`], so readers see either the old or the new
+/// Config in full, without locking. Reloading stops when this is dropped.
+#[derive(Debug)]
+pub struct SignalReload<TSolid> {
+    config: Arc<ArcSwap<TSolid>>,
+    handle: signal_hook::iterator::Handle,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl<TSolid> SignalReload<TSolid> {
+    /// Returns the latest Config that solidified successfully
+    pub fn current(&self) -> Arc<TSolid> {
+        self.config.load_full()
+    }
+
+    /// Returns the shared pointer the Config is published through, to hand to readers
+    pub fn shared(&self) -> Arc<ArcSwap<TSolid>> {
+        self.config.clone()
+    }
+}
+
+impl<TSolid> Drop for SignalReload<TSolid> {
+    fn drop(&mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl<TSolid> Builder<TSolid>
+where
+    TSolid: LayeredConfSolid + Send + Sync + 'static,
+    <TSolid>::Layer: LayeredConfLayer
+        + LayeredConfMerge<<TSolid>::Layer>
+        + LayeredConfSolidify<TSolid>
+        + std::fmt::Debug
+        + Default
+        + serde::de::DeserializeOwned
+        + LayeredConfArgs
+        + Send
+        + Sized,
+{
+    /// Solidifies the Builder, then loads, merges and solidifies all Layers again on SIGHUP
+    ///
+    /// `on_reload` is called with the result of each reload. The new Config is swapped in
+    /// when it solidifies, otherwise the previous Config stays in effect.
+    ///
+    /// ```rust,no_run
+    /// use std::path::PathBuf;
+    ///
+    /// use layeredconf::{Builder, Format, LayeredConf, Source};
+    ///
+    /// #[derive(LayeredConf, serde::Deserialize, Debug)]
+    /// struct Config {
+    ///     #[clap(long)]
+    ///     name: String,
+    /// }
+    ///
+    /// fn main() -> anyhow::Result<()> {
+    ///     let reload = Builder::<Config>::new()
+    ///         .new_layer(Source::File(PathBuf::from("config.yaml"), Format::Auto))
+    ///         .reload_on_sighup(|result| {
+    ///             if let Err(error) = result {
+    ///                 eprintln!("Reload failed, keeping old config: {}", error);
+    ///             }
+    ///         })?;
+    ///
+    ///     let config = reload.shared();
+    ///     println!("Running with {:?}", config.load());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn reload_on_sighup<F>(&self, mut on_reload: F) -> Result<SignalReload<TSolid>>
+    where
+        F: FnMut(Result<Arc<TSolid>>) + Send + 'static,
+    {
+        let builder = self.reloadable();
+        let config = Arc::new(ArcSwap::from_pointee(builder.solidify()?));
+
+        let mut signals =
+            Signals::new([SIGHUP]).map_err(|wrapped| Error::SignalError { wrapped })?;
+        let handle = signals.handle();
+
+        let thread_config = config.clone();
+        let thread = thread::spawn(move || {
+            for _ in signals.forever() {
+                let result = builder.solidify().map(Arc::new);
+                if let Ok(config) = &result {
+                    thread_config.store(config.clone());
+                }
+                on_reload(result);
+            }
+        });
+
+        Ok(SignalReload {
+            config,
+            handle,
+            thread: Some(thread),
+        })
+    }
+}
@@ -0,0 +1,231 @@
+//! Commented example config files, generated from a Config's doc comments
+
+use serde_json::Value;
+
+use crate::{
+    meta::{FieldMeta, StructMeta, ValueKind},
+    Error, Format, LayeredConfLayer, LayeredConfSolid, Result,
+};
+
+/// Commented example config file for a Config, e.g. for a `config.example.yaml`
+///
+/// Implemented for every `LayeredConf` Config, behind the `template` feature. Doc comments
+/// become comments, and required fields are marked. Fields with a default are filled in
+/// with it, other fields are commented out. Subconfigs are nested sections, and in TOML
+/// come after the fields of their parent. Optional subconfigs are commented out along with
+/// their defaults, as setting any of their fields enables them.
+///
+/// ```rust
+/// use layeredconf::{Format, LayeredConf, LayeredConfTemplate};
+///
+/// #[derive(LayeredConf, serde::Deserialize, serde::Serialize)]
+/// struct Config {
+///     /// Name of the instance
+///     #[clap(long)]
+///     name: String,
+///     /// Port to listen on
+///     #[layered(default = "default_port")]
+///     #[clap(long)]
+///     port: u16,
+/// }
+///
+/// fn default_port() -> u16 {
+///     8080
+/// }
+///
+/// assert_eq!(
+///     Config::template(Format::Yaml).unwrap(),
+///     "# Name of the instance\n# Required\n# name:\n\n# Port to listen on\nport: 8080\n",
+/// );
+/// ```
+pub trait LayeredConfTemplate {
+    /// Returns an example config file in `format`, which must be YAML or TOML
+    ///
+    /// Fails if the defaults can't be serialized as JSON, e.g. maps with non-string keys.
+    fn template(format: Format) -> Result<String>;
+}
+
+impl<T> LayeredConfTemplate for T
+where
+    T: LayeredConfSolid,
+    T::Layer: serde::Serialize,
+{
+    fn template(format: Format) -> Result<String> {
+        let meta = T::Layer::meta();
+        let defaults = serde_json::to_value(T::Layer::template_defaults())
+            .map_err(|wrapped| Error::DefaultsError { wrapped })?;
+
+        let mut out = String::new();
+        if let Some(doc) = meta.doc {
+            comment(&mut out, "", doc);
+            out.push('\n');
+        }
+
+        match format {
+            #[cfg(feature = "yaml")]
+            Format::Yaml => yaml_section(&mut out, "", &meta, &defaults),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml_section(&mut out, &[], &meta, &defaults),
+            format => return Err(Error::DumpUnsupported { format }),
+        }
+
+        Ok(out)
+    }
+}
+
+fn comment(out: &mut String, indent: &str, text: &str) {
+    for line in text.lines() {
+        if line.is_empty() {
+            out.push_str(&format!("{}#\n", indent));
+        } else {
+            out.push_str(&format!("{}# {}\n", indent, line));
+        }
+    }
+}
+
+/// Writes the field's doc comment, or its subconfig's, and whether it's required
+fn field_comment(out: &mut String, indent: &str, field: &FieldMeta) {
+    let doc = match (&field.kind, field.doc) {
+        (ValueKind::Subconfig(meta), None) => meta().doc,
+        (_, doc) => doc,
+    };
+    if let Some(doc) = doc {
+        comment(out, indent, doc);
+    }
+    if field.required {
+        comment(out, indent, "Required");
+    }
+}
+
+/// Whether any field of a subconfig, or its subconfigs, has a default
+#[cfg(feature = "yaml")]
+fn has_defaults(meta: &StructMeta, defaults: &Value) -> bool {
+    meta.fields.iter().any(|field| {
+        let default = defaults.get(field.key).unwrap_or(&Value::Null);
+        match &field.kind {
+            ValueKind::Subconfig(meta) => has_defaults(&meta(), default),
+            _ => !default.is_null(),
+        }
+    })
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_section(out: &mut String, indent: &str, meta: &StructMeta, defaults: &Value) {
+    for (i, field) in meta.fields.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        field_comment(out, indent, field);
+
+        let default = defaults.get(field.key).unwrap_or(&Value::Null);
+        match &field.kind {
+            ValueKind::Subconfig(sub_meta) => {
+                let sub_meta = sub_meta();
+                // A section without any values would be null, rather than an empty Layer
+                if has_defaults(&sub_meta, default) && !field.optional {
+                    out.push_str(&format!("{}{}:\n", indent, field.key));
+                } else {
+                    out.push_str(&format!("{}# {}:\n", indent, field.key));
+                }
+                let mut section = String::new();
+                yaml_section(&mut section, &format!("{}  ", indent), &sub_meta, default);
+                optional_section(out, field, &section);
+            }
+            // JSON values are valid YAML
+            _ if !default.is_null() => {
+                out.push_str(&format!("{}{}: {}\n", indent, field.key, default));
+            }
+            _ => out.push_str(&format!("{}# {}:\n", indent, field.key)),
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml_section(out: &mut String, path: &[&str], meta: &StructMeta, defaults: &Value) {
+    let (subconfigs, fields): (Vec<_>, Vec<_>) = meta
+        .fields
+        .iter()
+        .partition(|field| matches!(field.kind, ValueKind::Subconfig(_)));
+
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        field_comment(out, "", field);
+
+        match defaults.get(field.key).and_then(toml_value) {
+            Some(value) => out.push_str(&format!("{} = {}\n", field.key, value)),
+            None => out.push_str(&format!("# {} =\n", field.key)),
+        }
+    }
+
+    for field in subconfigs {
+        let sub_meta = match &field.kind {
+            ValueKind::Subconfig(sub_meta) => sub_meta(),
+            _ => unreachable!(),
+        };
+        let mut path = path.to_vec();
+        path.push(field.key);
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        field_comment(out, "", field);
+        let mut section = format!("[{}]\n", path.join("."));
+        toml_section(
+            &mut section,
+            &path,
+            &sub_meta,
+            defaults.get(field.key).unwrap_or(&Value::Null),
+        );
+        optional_section(out, field, &section);
+    }
+}
+
+/// Writes a subconfig's section, with its values commented out if it's optional, as setting
+/// any of them would enable it
+#[cfg(any(feature = "toml", feature = "yaml"))]
+fn optional_section(out: &mut String, field: &FieldMeta, section: &str) {
+    if !field.optional {
+        out.push_str(section);
+        return;
+    }
+    for line in section.lines() {
+        let value = line.trim_start();
+        if value.is_empty() || value.starts_with('#') {
+            out.push_str(line);
+        } else {
+            out.push_str(&format!("{}# {}", &line[..line.len() - value.len()], value));
+        }
+        out.push('\n');
+    }
+}
+
+/// Renders a default as a TOML value, if TOML can represent it
+#[cfg(feature = "toml")]
+fn toml_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        // JSON's string escapes are valid in TOML basic strings
+        Value::String(_) => Some(value.to_string()),
+        Value::Array(items) => {
+            let items = items.iter().map(toml_value).collect::<Option<Vec<_>>>()?;
+            Some(format!("[{}]", items.join(", ")))
+        }
+        Value::Object(entries) if entries.is_empty() => Some("{}".to_string()),
+        Value::Object(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| {
+                    Some(format!(
+                        "{} = {}",
+                        Value::String(key.clone()),
+                        toml_value(value)?
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("{{ {} }}", entries.join(", ")))
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Checks run on a Config's values at solidify time
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    ffi::OsString,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{location, LayeredConfSolid, LayeredConfSolidify, Location};
+
+/// A value that failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationFailure {
+    /// Dotted path of the field by its keys, e.g. `db.uri`, or of the struct for struct level
+    /// checks
+    pub path: String,
+    /// Why the value is invalid
+    pub message: String,
+    /// Where the value was set, when it was set by a Layer
+    pub location: Option<Location>,
+}
+
+impl std::fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(
+                f,
+                "{}: {}{}",
+                self.path,
+                self.message,
+                location::at(self.location.as_ref())
+            )
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn push(
+    failures: &mut Vec<ValidationFailure>,
+    prefix: &str,
+    name: &str,
+    result: Result<(), String>,
+) {
+    if let Err(message) = result {
+        failures.push(ValidationFailure {
+            path: crate::field_path(prefix, name),
+            message,
+            location: None,
+        });
+    }
+}
+
+#[doc(hidden)]
+pub fn subconfig<T>(solid: &T, prefix: &str, failures: &mut Vec<ValidationFailure>)
+where
+    T: LayeredConfSolid,
+    T::Layer: LayeredConfSolidify<T>,
+{
+    T::Layer::validate_into(solid, prefix, failures);
+}
+
+#[doc(hidden)]
+pub fn push_struct(
+    failures: &mut Vec<ValidationFailure>,
+    prefix: &str,
+    result: Result<(), String>,
+) {
+    if let Err(message) = result {
+        failures.push(ValidationFailure {
+            path: prefix.to_string(),
+            message,
+            location: None,
+        });
+    }
+}
+
+#[doc(hidden)]
+pub fn custom<E: Display>(result: Result<(), E>) -> Result<(), String> {
+    result.map_err(|error| error.to_string())
+}
+
+#[doc(hidden)]
+pub fn range<T>(value: &T, min: Option<T>, max: Option<T>) -> Result<(), String>
+where
+    T: PartialOrd + Display,
+{
+    let too_low = min.as_ref().is_some_and(|min| value < min);
+    let too_high = max.as_ref().is_some_and(|max| value > max);
+    if !too_low && !too_high {
+        return Ok(());
+    }
+
+    Err(match (min, max) {
+        (Some(min), Some(max)) => format!("must be between {} and {}, got {}", min, max, value),
+        (Some(min), None) => format!("must be at least {}, got {}", min, value),
+        (None, Some(max)) => format!("must be at most {}, got {}", max, value),
+        (None, None) => unreachable!(),
+    })
+}
+
+#[doc(hidden)]
+pub fn non_empty<T: IsEmpty + ?Sized>(value: &T) -> Result<(), String> {
+    if value.is_empty() {
+        Err("must not be empty".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// A `#[layered(regex = "...")]` pattern, compiled on first use
+#[doc(hidden)]
+#[cfg(feature = "regex")]
+pub type CompiledRegex = Result<regex::Regex, String>;
+
+#[doc(hidden)]
+#[cfg(feature = "regex")]
+pub fn regex<T: AsRef<str> + ?Sized>(
+    value: &T,
+    compiled: &std::sync::OnceLock<CompiledRegex>,
+    pattern: &str,
+) -> Result<(), String> {
+    let regex = compiled
+        .get_or_init(|| {
+            regex::Regex::new(pattern)
+                .map_err(|error| format!("invalid pattern {:?}: {}", pattern, error))
+        })
+        .as_ref()
+        .map_err(Clone::clone)?;
+    if regex.is_match(value.as_ref()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} doesn't match pattern {:?}",
+            value.as_ref(),
+            pattern
+        ))
+    }
+}
+
+/// Checks a `#[layered(regex = "...")]` field, compiling its pattern once per field
+#[doc(hidden)]
+#[cfg(feature = "regex")]
+#[macro_export]
+macro_rules! validate_regex {
+    ($value:expr, $pattern:expr) => {{
+        static COMPILED: std::sync::OnceLock<$crate::validate::CompiledRegex> =
+            std::sync::OnceLock::new();
+        $crate::validate::regex($value, &COMPILED, $pattern)
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "regex"))]
+#[macro_export]
+macro_rules! validate_regex {
+    ($value:expr, $pattern:expr) => {
+        compile_error!("#[layered(regex = \"...\")] needs the `regex` feature of layeredconf")
+    };
+}
+
+#[doc(hidden)]
+pub fn path_exists<T: AsRef<Path> + ?Sized>(value: &T) -> Result<(), String> {
+    let path = value.as_ref();
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(format!("path {:?} doesn't exist", path))
+    }
+}
+
+/// Values that can be checked with `non_empty`
+#[doc(hidden)]
+pub trait IsEmpty {
+    fn is_empty(&self) -> bool;
+}
+
+impl IsEmpty for str {
+    fn is_empty(&self) -> bool {
+        str::is_empty(self)
+    }
+}
+
+impl IsEmpty for String {
+    fn is_empty(&self) -> bool {
+        String::is_empty(self)
+    }
+}
+
+impl IsEmpty for OsString {
+    fn is_empty(&self) -> bool {
+        self.as_os_str().is_empty()
+    }
+}
+
+impl IsEmpty for Path {
+    fn is_empty(&self) -> bool {
+        self.as_os_str().is_empty()
+    }
+}
+
+impl IsEmpty for PathBuf {
+    fn is_empty(&self) -> bool {
+        self.as_os_str().is_empty()
+    }
+}
+
+impl<T> IsEmpty for Vec<T> {
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+}
+
+impl<T> IsEmpty for VecDeque<T> {
+    fn is_empty(&self) -> bool {
+        VecDeque::is_empty(self)
+    }
+}
+
+impl<T, S> IsEmpty for HashSet<T, S> {
+    fn is_empty(&self) -> bool {
+        HashSet::is_empty(self)
+    }
+}
+
+impl<T> IsEmpty for BTreeSet<T> {
+    fn is_empty(&self) -> bool {
+        BTreeSet::is_empty(self)
+    }
+}
+
+impl<K, V, S> IsEmpty for HashMap<K, V, S> {
+    fn is_empty(&self) -> bool {
+        HashMap::is_empty(self)
+    }
+}
+
+impl<K, V> IsEmpty for BTreeMap<K, V> {
+    fn is_empty(&self) -> bool {
+        BTreeMap::is_empty(self)
+    }
+}
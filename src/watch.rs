@@ -0,0 +1,304 @@
+//! Reloads a Config when its files change
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    Builder, Error, LayeredConfArgs, LayeredConfLayer, LayeredConfMerge, LayeredConfSolid,
+    LayeredConfSolidify, Result,
+};
+
+/// How long files must be left alone before reloading, as editors often save in several steps
+const SETTLE: Duration = Duration::from_millis(50);
+
+enum Message {
+    Event(notify::Result<notify::Event>),
+    Stop,
+}
+
+/// A Config that's reloaded when its files change, returned by [`Builder::watch`]
+///
+/// Watching stops when this is dropped.
+#[derive(Debug)]
+pub struct Watch<TSolid> {
+    current: Arc<Mutex<Arc<TSolid>>>,
+    sender: mpsc::Sender<Message>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl<TSolid> Watch<TSolid> {
+    /// Returns the latest Config that solidified successfully
+    pub fn current(&self) -> Arc<TSolid> {
+        self.current.lock().unwrap().clone()
+    }
+}
+
+impl<TSolid> Drop for Watch<TSolid> {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Message::Stop);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Event(event) => f.debug_tuple("Event").field(event).finish(),
+            Message::Stop => f.write_str("Stop"),
+        }
+    }
+}
+
+impl<TSolid> Builder<TSolid>
+where
+    TSolid: LayeredConfSolid + Send + Sync + 'static,
+    <TSolid>::Layer: LayeredConfLayer
+        + LayeredConfMerge<<TSolid>::Layer>
+        + LayeredConfSolidify<TSolid>
+        + std::fmt::Debug
+        + Default
+        + serde::de::DeserializeOwned
+        + LayeredConfArgs
+        + Send
+        + Sized,
+{
+    /// Solidifies the Builder, then reloads the Config whenever one of its files changes
+    ///
+    /// Files of `Source::File` and `Source::FileOptional` Layers are watched, along with
+    /// the files they load with `load_config`. On a change all Layers are loaded, merged
+    /// and solidified again, and `on_reload` is called with the new Config or the error.
+    /// [`Watch::current`] keeps returning the previous Config when reloading fails.
+    /// Errors from watching the files are passed to `on_reload` as [`Error::WatchError`].
+    /// Sending the result down a channel in `on_reload` hands it to another thread.
+    ///
+    /// ```rust,no_run
+    /// use std::path::PathBuf;
+    ///
+    /// use layeredconf::{Builder, Format, LayeredConf, Source};
+    ///
+    /// #[derive(LayeredConf, serde::Deserialize, Debug)]
+    /// struct Config {
+    ///     #[clap(long)]
+    ///     name: String,
+    /// }
+    ///
+    /// fn main() -> anyhow::Result<()> {
+    ///     let watch = Builder::<Config>::new()
+    ///         .new_layer(Source::File(PathBuf::from("config.yaml"), Format::Auto))
+    ///         .watch(|result| match result {
+    ///             Ok(config) => println!("Reloaded {:?}", config),
+    ///             Err(error) => eprintln!("Reload failed, keeping old config: {}", error),
+    ///         })?;
+    ///
+    ///     println!("Running with {:?}", watch.current());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn watch<F>(&self, mut on_reload: F) -> Result<Watch<TSolid>>
+    where
+        F: FnMut(Result<Arc<TSolid>>) + Send + 'static,
+    {
+        let builder = self.reloadable();
+        let current = Arc::new(Mutex::new(Arc::new(builder.solidify()?)));
+
+        let (sender, receiver) = mpsc::channel();
+        let event_sender = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = event_sender.send(Message::Event(event));
+        })?;
+        let mut watched = Watched::default();
+        watched.update(&mut watcher, builder.file_paths()?)?;
+
+        let thread_current = current.clone();
+        let thread = thread::spawn(move || {
+            while let Some(change) = watched.next_change(&receiver) {
+                // The watch may be broken, so the caller is told rather than reloads quietly
+                // stopping
+                if let Err(error) = change {
+                    on_reload(Err(error));
+                    continue;
+                }
+
+                let result = builder.solidify().map(Arc::new);
+                if let Ok(config) = &result {
+                    *thread_current.lock().unwrap() = config.clone();
+                }
+
+                // The files loaded with `load_config` may have changed too, watch them
+                // before reporting so that no later change is missed
+                let watch_result = builder
+                    .file_paths()
+                    .and_then(|files| watched.update(&mut watcher, files));
+
+                on_reload(result);
+                if let Err(error) = watch_result {
+                    on_reload(Err(error));
+                }
+            }
+        });
+
+        Ok(Watch {
+            current,
+            sender,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// The watched files, and the directories containing them
+///
+/// Directories are watched rather than the files themselves, to notice files that are
+/// replaced by renaming over them, or that don't exist yet. The nearest existing ancestor of
+/// a directory that doesn't exist yet is watched until it's created. Paths are normalized,
+/// and the targets of symlinked files watched too, so that events match however a file is
+/// reached.
+#[derive(Debug, Default)]
+struct Watched {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+    /// An error from the watcher while a change settled, reported after reloading
+    error: Option<notify::Error>,
+}
+
+impl Watched {
+    /// Waits for a change to the watched files and for it to settle, returning the
+    /// watcher's errors as they come, or `None` once stopped
+    fn next_change(&mut self, receiver: &mpsc::Receiver<Message>) -> Option<Result<()>> {
+        if let Some(wrapped) = self.error.take() {
+            return Some(Err(Error::WatchError { wrapped }));
+        }
+        loop {
+            match receiver.recv() {
+                Ok(Message::Event(Ok(event))) if self.is_relevant(&event) => break,
+                Ok(Message::Event(Ok(_))) => continue,
+                Ok(Message::Event(Err(wrapped))) => {
+                    return Some(Err(Error::WatchError { wrapped }))
+                }
+                Ok(Message::Stop) | Err(_) => return None,
+            }
+        }
+        loop {
+            match receiver.recv_timeout(SETTLE) {
+                Ok(Message::Event(Ok(_))) => continue,
+                Ok(Message::Event(Err(error))) => {
+                    self.error = Some(error);
+                    return Some(Ok(()));
+                }
+                Err(RecvTimeoutError::Timeout) => return Some(Ok(())),
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    fn update(&mut self, watcher: &mut RecommendedWatcher, files: Vec<PathBuf>) -> Result<()> {
+        let files = files
+            .iter()
+            .flat_map(|file| {
+                let target = file.canonicalize().ok();
+                [Some(normalize(file)), target].into_iter().flatten()
+            })
+            .collect::<HashSet<_>>();
+        let dirs = files
+            .iter()
+            .filter_map(|file| file.ancestors().skip(1).find(|dir| dir.is_dir()))
+            .map(Path::to_path_buf)
+            .collect::<HashSet<_>>();
+
+        for dir in self.dirs.difference(&dirs) {
+            let _ = watcher.unwatch(dir);
+        }
+        for dir in dirs.difference(&self.dirs) {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        }
+
+        self.files = files;
+        self.dirs = dirs;
+        Ok(())
+    }
+
+    /// Whether the event changed a watched file, or created a directory that's on the way
+    /// to one, which then has to be watched
+    fn is_relevant(&self, event: &notify::Event) -> bool {
+        !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|path| {
+                let path = normalize(path);
+                self.files.contains(&path)
+                    || (!self.dirs.contains(&path)
+                        && self.files.iter().any(|file| file.starts_with(&path)))
+            })
+    }
+}
+
+/// `path` with the part of its directory that exists canonicalized, so it's the same
+/// whether it's reached through symlinks or `..`
+fn normalize(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            match parent.canonicalize() {
+                Ok(parent) => parent.join(name),
+                Err(_) => normalize(parent).join(name),
+            }
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_change_error() {
+        let (sender, receiver) = mpsc::channel();
+        let mut watched = Watched::default();
+
+        sender
+            .send(Message::Event(Err(notify::Error::generic("overflow"))))
+            .unwrap();
+        sender.send(Message::Stop).unwrap();
+
+        match watched.next_change(&receiver) {
+            Some(Err(Error::WatchError { wrapped })) => {
+                assert!(wrapped.to_string().contains("overflow"))
+            }
+            other => panic!("Unexpected change {:?}", other),
+        }
+        assert!(watched.next_change(&receiver).is_none());
+    }
+
+    #[test]
+    fn test_next_change_error_while_settling() {
+        let (sender, receiver) = mpsc::channel();
+        let mut watched = Watched::default();
+        let file = normalize(Path::new("config.yaml"));
+        watched.files.insert(file.clone());
+
+        let event = notify::Event::new(EventKind::Any).add_path(file);
+        sender.send(Message::Event(Ok(event))).unwrap();
+        sender
+            .send(Message::Event(Err(notify::Error::generic("overflow"))))
+            .unwrap();
+        sender.send(Message::Stop).unwrap();
+
+        // The change is reloaded before the error is reported
+        assert!(matches!(watched.next_change(&receiver), Some(Ok(()))));
+        assert!(matches!(
+            watched.next_change(&receiver),
+            Some(Err(Error::WatchError { .. }))
+        ));
+        assert!(watched.next_change(&receiver).is_none());
+    }
+}
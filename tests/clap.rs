@@ -3,7 +3,7 @@ use std::time::Duration;
 use clap::IntoApp;
 use serde::{Deserialize, Serialize};
 
-use layeredconf::LayeredConf;
+use layeredconf::{Builder, LayeredConf, Source};
 
 #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
 /// Clap app description
@@ -49,3 +49,24 @@ fn test_clap_help_via_app() {
     assert!(!string.contains("Subconfig"));
     assert!(!string.contains("Duration"));
 }
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct UnsetFieldConfig {
+    #[clap(long)]
+    unset: Option<String>,
+}
+
+#[test]
+fn test_field_named_unset() -> anyhow::Result<()> {
+    // Without `#[layered(unset_flag)]`, `--unset` is free for the Config's own fields
+    let config: UnsetFieldConfig = Builder::new()
+        .new_layer(Source::ArgumentsFrom(vec![
+            "app".to_string(),
+            "--unset".to_string(),
+            "value".to_string(),
+        ]))
+        .solidify()?;
+    assert_eq!(config.unset.as_deref(), Some("value"));
+
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{
+    Builder, Error, Format, LayeredConf, LayeredConfDiff, MissingField, Result, Source, UnknownKey,
+};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+    #[layered(subconfig)]
+    upstreams: HashMap<String, Upstream>,
+    #[layered(subconfig, merge = "append")]
+    listeners: Vec<Listener>,
+    #[layered(subconfig)]
+    routes: BTreeMap<String, Route>,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[layered(subconfig)]
+struct Upstream {
+    #[layered(non_empty)]
+    #[clap(long)]
+    url: String,
+    #[layered(default = "default_timeout")]
+    #[clap(long)]
+    timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[layered(subconfig)]
+struct Listener {
+    #[clap(long)]
+    port: u16,
+    #[layered(default)]
+    #[clap(long)]
+    tls: bool,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[layered(subconfig)]
+struct Route {
+    #[clap(long)]
+    upstream: String,
+}
+
+const BASE: &str = r#"
+name: proxy
+upstreams:
+  primary:
+    url: http://primary
+    timeout: 5
+  backup:
+    url: http://backup
+listeners:
+  - port: 80
+routes:
+  api:
+    upstream: primary
+"#;
+
+#[test]
+fn test_merge_entries() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "[upstreams.primary]\nurl = \"http://override\"\n\n[[listeners]]\nport = 443\ntls = true\n"
+                .to_string(),
+            Format::Toml,
+        ))
+        .solidify()?;
+
+    // Keys are merged one by one, keeping the fields a higher Layer doesn't set
+    assert_eq!(config.upstreams.len(), 2);
+    assert_eq!(
+        config.upstreams["primary"],
+        Upstream {
+            url: "http://override".to_string(),
+            timeout: 5,
+        }
+    );
+    assert_eq!(
+        config.upstreams["backup"],
+        Upstream {
+            url: "http://backup".to_string(),
+            timeout: 30,
+        }
+    );
+
+    // Entries of a Vec follow its merge strategy, with each entry's own defaults
+    assert_eq!(
+        config.listeners,
+        vec![
+            Listener {
+                port: 80,
+                tls: false,
+            },
+            Listener {
+                port: 443,
+                tls: true,
+            },
+        ]
+    );
+    assert_eq!(config.routes["api"].upstream, "primary");
+
+    Ok(())
+}
+
+#[test]
+fn test_empty() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String("name: proxy".to_string(), Format::Yaml))
+        .solidify()?;
+    assert!(config.upstreams.is_empty());
+    assert!(config.listeners.is_empty());
+    assert!(config.routes.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_missing() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            r#"{"name": "proxy", "upstreams": {"primary": {"timeout": 5}}, "listeners": [{"port": 80}, {"tls": true}]}"#
+                .to_string(),
+            Format::Json,
+        ))
+        .solidify();
+    match result {
+        Err(Error::SolidifyFailedMissing { missing }) => assert_eq!(
+            missing,
+            vec![
+                MissingField {
+                    path: "upstreams.primary.url".to_string(),
+                    flag: None,
+                },
+                MissingField {
+                    path: "listeners.1.port".to_string(),
+                    flag: None,
+                },
+            ]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_unset() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "_unset: [upstreams.backup, upstreams.primary.timeout, listeners]".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.upstreams.len(), 1);
+    assert_eq!(config.upstreams["primary"].timeout, 30);
+    assert!(config.listeners.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_validate() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "upstreams:\n  backup:\n    url: ''\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].path, "upstreams.backup.url");
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict() {
+    let result: Result<Config> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(
+            "name: proxy\nupstreams:\n  primary:\n    urll: http://primary\nlisteners:\n  - port: 80\n    tlss: true\n"
+                .to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "upstreams.primary.urll (did you mean url?) at <string>:4:5",
+                "listeners.0.tlss (did you mean tls?) at <string>:7:5",
+            ]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict_list_entries() {
+    let unknown = |string: &str, format| {
+        let result: Result<Config> = Builder::new()
+            .strict(true)
+            .new_layer(Source::String(string.to_string(), format))
+            .solidify();
+        match result {
+            Err(Error::UnknownKeys { unknown }) => unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    };
+
+    // Each entry's key is found in that entry, not in the first entry that has it
+    assert_eq!(
+        unknown(
+            "name: proxy\nlisteners:\n  - port: 80\n    tlss: true\n  - port: 443\n    tlss: true\n",
+            Format::Yaml
+        ),
+        vec![
+            "listeners.0.tlss (did you mean tls?) at <string>:4:5",
+            "listeners.1.tlss (did you mean tls?) at <string>:6:5",
+        ]
+    );
+    assert_eq!(
+        unknown(
+            r#"{"name": "proxy", "listeners": [{"port": 80, "tlss": true}, {"port": 443, "tlss": true}]}"#,
+            Format::Json
+        ),
+        vec![
+            "listeners.0.tlss (did you mean tls?) at <string>:1:47",
+            "listeners.1.tlss (did you mean tls?) at <string>:1:76",
+        ]
+    );
+    assert_eq!(
+        unknown(
+            "name = \"proxy\"\n\n[[listeners]]\nport = 80\ntlss = true\n\n[[listeners]]\nport = 443\ntlss = true\n",
+            Format::Toml
+        ),
+        vec![
+            "listeners.0.tlss (did you mean tls?) at <string>:5:1",
+            "listeners.1.tlss (did you mean tls?) at <string>:9:1",
+        ]
+    );
+}
+
+#[test]
+fn test_diff() -> anyhow::Result<()> {
+    let old: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .solidify()?;
+    let new: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "upstreams:\n  primary:\n    timeout: 10\n  canary:\n    url: http://canary\n"
+                .to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+
+    let changes = old
+        .diff(&new)
+        .iter()
+        .map(|change| change.path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        vec!["upstreams.canary", "upstreams.primary.timeout"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_defaults() -> anyhow::Result<()> {
+    let mut builder = Builder::<Config>::new();
+    builder.new_layer(Source::String(
+        "name = 'proxy'\n\n[upstreams.a]\nurl = 'x'\n\n[[listeners]]\nport = 80\n".to_string(),
+        Format::Toml,
+    ));
+
+    // Each entry is dumped with its defaults, as it's solidified
+    assert_eq!(
+        builder.dump(Format::Toml, true)?,
+        "name = 'proxy'\n\n[[listeners]]\nport = 80\ntls = false\n[upstreams.a]\ntimeout = 30\nurl = 'x'\n"
+    );
+    let config = builder.solidify()?;
+    assert_eq!(config.upstreams["a"].timeout, 30);
+    assert!(!config.listeners[0].tls);
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::fs;
+
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme};
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Format, LayeredConf, Result, Source};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[layered(range(min = 1))]
+    #[clap(long)]
+    port: u16,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[layered(non_empty)]
+    #[clap(long)]
+    uri: String,
+}
+
+fn render(diagnostic: &dyn Diagnostic) -> String {
+    let mut rendered = String::new();
+    GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+        .with_width(200)
+        .render_report(&mut rendered, diagnostic)
+        .unwrap();
+    rendered
+}
+
+#[test]
+fn test_parse_error_diagnostic() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.yaml");
+    fs::write(&path, "port: eighty\ndb:\n  uri: db\n")?;
+
+    let error = Builder::<Config>::new()
+        .new_layer(Source::File(path.clone(), Format::Auto))
+        .solidify()
+        .unwrap_err();
+
+    assert_eq!(error.code().unwrap().to_string(), "layeredconf::yaml");
+    let rendered = render(&error);
+    assert!(rendered.contains(&format!("[{}:1:1]", path.canonicalize()?.display())));
+    assert!(rendered.contains(" 1 │ port: eighty\n"));
+    assert!(rendered.contains("      ───┬──\n"));
+    assert!(rendered.contains("invalid type: string \"eighty\", expected u16"));
+    assert!(rendered.contains("help: check this is valid YAML, with values of the fields' types"));
+
+    Ok(())
+}
+
+#[test]
+fn test_validation_diagnostic() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            "port = 0\n\n[db]\nuri = \"\"\n".to_string(),
+            Format::Toml,
+        ))
+        .solidify();
+    let error = result.unwrap_err();
+
+    let related = error.related().unwrap().collect::<Vec<_>>();
+    assert_eq!(related.len(), 2);
+
+    let rendered = render(related[1]);
+    assert!(rendered.contains("[<string>:3:1]"));
+    assert!(rendered.contains(" 4 │ uri = \"\"\n"));
+    assert!(rendered.contains("must not be empty"));
+    assert!(rendered.contains("help: change `db.uri` here, or override it with a later Layer"));
+
+    let rendered = render(&error);
+    assert!(rendered.contains(" 1 │ port = 0\n"));
+    assert!(rendered.contains("must be at least 1, got 0"));
+}
+
+#[test]
+fn test_unknown_key_diagnostic() {
+    let result: Result<Config> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(
+            "port: 80\ndb:\n  urii: db\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    let error = result.unwrap_err();
+    assert_eq!(
+        error.code().unwrap().to_string(),
+        "layeredconf::unknown_key"
+    );
+
+    let related = error.related().unwrap().collect::<Vec<_>>();
+    let rendered = render(related[0]);
+    assert!(rendered.contains(" 3 │   urii: db\n"));
+    assert!(rendered.contains("not a field of the config"));
+    assert!(rendered.contains("help: did you mean `uri`?"));
+}
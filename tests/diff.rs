@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Change, LayeredConf, LayeredConfDiff};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+    #[clap(long)]
+    optional: Option<String>,
+    #[layered(subconfig)]
+    subconfig: SubConfig,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct SubConfig {
+    #[clap(long)]
+    flibble: u64,
+    #[layered(subconfig)]
+    nested: Nested,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Nested {
+    #[clap(long)]
+    list: Vec<u32>,
+}
+
+fn config(name: &str, optional: Option<&str>, flibble: u64, list: Vec<u32>) -> Config {
+    Config {
+        name: name.to_string(),
+        optional: optional.map(str::to_string),
+        subconfig: SubConfig {
+            flibble,
+            nested: Nested { list },
+        },
+    }
+}
+
+#[test]
+fn test_diff_config() {
+    let old = config("same", None, 10, vec![1]);
+
+    assert_eq!(old.diff(&old.clone()), vec![]);
+
+    let new = config("same", Some("set"), 12, vec![1, 2]);
+    let changes = old.diff(&new);
+
+    assert_eq!(
+        changes,
+        vec![
+            Change {
+                path: "optional".to_string(),
+                old: None,
+                new: Some("\"set\"".to_string()),
+            },
+            Change {
+                path: "subconfig.flibble".to_string(),
+                old: Some("10".to_string()),
+                new: Some("12".to_string()),
+            },
+            Change {
+                path: "subconfig.nested.list".to_string(),
+                old: Some("[1]".to_string()),
+                new: Some("[1, 2]".to_string()),
+            },
+        ]
+    );
+    assert_eq!(
+        changes.iter().map(Change::to_string).collect::<Vec<_>>(),
+        vec![
+            "optional: (unset) -> \"set\"",
+            "subconfig.flibble: 10 -> 12",
+            "subconfig.nested.list: [1] -> [1, 2]",
+        ]
+    );
+}
+
+#[test]
+fn test_diff_layer() -> anyhow::Result<()> {
+    let old: ConfigLayer =
+        serde_json::from_str(r#"{"name": "old", "subconfig": {"flibble": 10}}"#)?;
+    let new: ConfigLayer = serde_json::from_str(r#"{"subconfig": {"flibble": 12}}"#)?;
+
+    assert_eq!(
+        old.diff(&new)
+            .iter()
+            .map(Change::to_string)
+            .collect::<Vec<_>>(),
+        vec!["name: \"old\" -> (unset)", "subconfig.flibble: 10 -> 12"]
+    );
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Renamed {
+    pool_size: u32,
+    #[serde(rename = "db")]
+    #[layered(subconfig)]
+    database: RenamedDb,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+#[serde(rename_all = "kebab-case")]
+struct RenamedDb {
+    max_connections: u32,
+}
+
+#[test]
+fn test_diff_renamed() -> anyhow::Result<()> {
+    let old: RenamedLayer =
+        serde_json::from_str(r#"{"poolSize": 1, "db": {"max-connections": 2}}"#)?;
+    let new: RenamedLayer = serde_json::from_str(r#"{"db": {"max-connections": 3}}"#)?;
+
+    assert_eq!(
+        old.diff(&new)
+            .iter()
+            .map(Change::to_string)
+            .collect::<Vec<_>>(),
+        vec!["poolSize: 1 -> (unset)", "db.max-connections: 2 -> 3"]
+    );
+
+    Ok(())
+}
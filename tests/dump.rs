@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Error, Format, LayeredConf, Source};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(unset_flag)]
+struct Config {
+    #[clap(long)]
+    name: String,
+    #[layered(default = "default_port")]
+    #[clap(long)]
+    port: u16,
+    #[clap(long)]
+    optional: Option<String>,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[clap(long)]
+    uri: String,
+    #[layered(default)]
+    #[clap(long)]
+    pool_size: u32,
+}
+
+fn builder() -> Builder<Config> {
+    let mut builder = Builder::new();
+    builder
+        .new_layer(Source::String(
+            r#"{"name": "json", "optional": "json", "db": {"uri": "json"}}"#.to_string(),
+            Format::Json,
+        ))
+        .new_layer(Source::ArgumentsFrom(vec![
+            "dump".to_string(),
+            "--name".to_string(),
+            "args".to_string(),
+            "--unset".to_string(),
+            "optional".to_string(),
+        ]));
+    builder
+}
+
+#[test]
+fn test_merged_layer() -> anyhow::Result<()> {
+    let merged = builder().merged_layer()?;
+
+    assert_eq!(
+        serde_json::to_string(&merged)?,
+        r#"{"name":"args","db":{"uri":"json"}}"#
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dump() -> anyhow::Result<()> {
+    let builder = builder();
+
+    assert_eq!(
+        builder.dump(Format::Json, false)?,
+        "{\n  \"name\": \"args\",\n  \"db\": {\n    \"uri\": \"json\"\n  }\n}"
+    );
+    assert_eq!(
+        builder.dump(Format::Yaml, true)?,
+        "---\nname: args\nport: 8080\ndb:\n  uri: json\n  pool_size: 0\n"
+    );
+    assert_eq!(
+        builder.dump(Format::Toml, true)?,
+        "name = 'args'\nport = 8080\n\n[db]\npool_size = 0\nuri = 'json'\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_roundtrip() -> anyhow::Result<()> {
+    let dumped = builder().dump(Format::Toml, false)?;
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(dumped, Format::Toml))
+        .solidify()?;
+
+    assert_eq!(config.name, "args");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.optional, None);
+    assert_eq!(config.db.uri, "json");
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_unsupported() {
+    match builder().dump(Format::Auto, false) {
+        Err(Error::DumpUnsupported {
+            format: Format::Auto,
+        }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{
+    Builder, Error, Format, LayeredConf, LayeredConfDiff, MissingField, Result, Source, UnknownKey,
+    ValueSource,
+};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+    #[layered(subconfig)]
+    storage: Storage,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[layered(subconfig)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Storage {
+    S3 {
+        #[layered(non_empty)]
+        #[clap(long)]
+        bucket: String,
+        #[layered(default = "default_region")]
+        #[clap(long)]
+        region: String,
+    },
+    Local {
+        #[clap(long)]
+        path: String,
+        #[clap(long)]
+        quota: Option<u64>,
+    },
+    Memory,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+const BASE: &str = "name: app\nstorage:\n  type: s3\n  bucket: lower\n";
+
+#[test]
+fn test_override_fields() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "[storage]\nbucket = \"higher\"\n".to_string(),
+            Format::Toml,
+        ))
+        .solidify()?;
+    assert_eq!(
+        config.storage,
+        Storage::S3 {
+            bucket: "higher".to_string(),
+            region: "us-east-1".to_string(),
+        }
+    );
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::ArgumentsFrom(args(&[
+            "app",
+            "--region",
+            "eu-west-2",
+        ])))
+        .solidify()?;
+    assert_eq!(
+        config.storage,
+        Storage::S3 {
+            bucket: "lower".to_string(),
+            region: "eu-west-2".to_string(),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_switch_variant() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            r#"{"storage": {"type": "local", "path": "/data"}}"#.to_string(),
+            Format::Json,
+        ))
+        .solidify()?;
+    assert_eq!(
+        config.storage,
+        Storage::Local {
+            path: "/data".to_string(),
+            quota: None,
+        }
+    );
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "storage:\n  type: memory\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.storage, Storage::Memory);
+
+    Ok(())
+}
+
+#[test]
+fn test_switch_variant_provenance() -> anyhow::Result<()> {
+    let higher = Source::String(
+        r#"{"storage": {"type": "local", "path": "/data"}}"#.to_string(),
+        Format::Json,
+    );
+    let (_, provenance) = Builder::<Config>::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(higher.clone())
+        .solidify_with_provenance()?;
+
+    // The lower Layer of another variant isn't merged, nor the defaults of its fields
+    let from_higher = ValueSource::Layer {
+        source: higher,
+        parents: vec![],
+    };
+    assert_eq!(provenance.get("storage.type"), Some(&from_higher));
+    assert_eq!(provenance.get("storage.path"), Some(&from_higher));
+    assert_eq!(provenance.get("storage.bucket"), None);
+    assert_eq!(provenance.get("storage.region"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_missing() {
+    // Only the fields of the selected variant are required, and a lower Layer of another
+    // variant doesn't fill them in
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            "name: app\nstorage:\n  type: local\n  path: /data\n".to_string(),
+            Format::Yaml,
+        ))
+        .new_layer(Source::String(
+            "storage:\n  type: s3\n  region: eu-west-2\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::SolidifyFailedMissing { missing }) => assert_eq!(
+            missing,
+            vec![MissingField {
+                path: "storage.bucket".to_string(),
+                flag: Some("--bucket".to_string()),
+            }]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            "name: app\nstorage:\n  bucket: data\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::SolidifyFailedMissing { missing }) => assert_eq!(
+            missing,
+            vec![MissingField {
+                path: "storage.type".to_string(),
+                flag: None,
+            }]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_unknown_variant() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            "name: app\nstorage:\n  type: gcs\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_validate() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(BASE.to_string(), Format::Yaml))
+        .new_layer(Source::String(
+            "storage:\n  bucket: ''\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].path, "storage.bucket");
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict() {
+    let result: Result<Config> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(
+            "name: app\nstorage:\n  type: s3\n  buckett: data\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            vec!["storage.buckett (did you mean bucket?) at <string>:4:3"]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict_variant() {
+    // Keys are checked against the fields of the variant the Layer selects
+    let result: Result<Config> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(
+            "name: app\nstorage:\n  type: local\n  path: /data\n  bucket: data\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            vec!["storage.bucket at <string>:5:3"]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_diff() {
+    let s3 = Storage::S3 {
+        bucket: "data".to_string(),
+        region: "us-east-1".to_string(),
+    };
+    let old = Config {
+        name: "app".to_string(),
+        storage: s3.clone(),
+    };
+    let moved = Config {
+        name: "app".to_string(),
+        storage: Storage::S3 {
+            bucket: "data".to_string(),
+            region: "eu-west-2".to_string(),
+        },
+    };
+    let local = Config {
+        name: "app".to_string(),
+        storage: Storage::Local {
+            path: "/data".to_string(),
+            quota: None,
+        },
+    };
+
+    let changes = old.diff(&moved);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(
+        changes[0].to_string(),
+        "storage.region: \"us-east-1\" -> \"eu-west-2\""
+    );
+
+    let changes = old.diff(&local);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, "storage");
+    assert_eq!(changes[0].old, Some(format!("{:?}", s3)));
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Error, Format, LayeredConf, Result, Source};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+    #[clap(long)]
+    port: u16,
+    #[clap(long)]
+    optional: Option<String>,
+    #[clap(long)]
+    list: Vec<String>,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[clap(long)]
+    uri: String,
+    #[clap(long)]
+    pool_size: u32,
+}
+
+fn vars(vars: &[(&str, &str)]) -> HashMap<String, String> {
+    vars.iter()
+        .map(|(var, value)| (var.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_environment() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::EnvironmentFrom(
+            Some("MYAPP_".to_string()),
+            vars(&[
+                ("MYAPP_NAME", "env_name"),
+                ("MYAPP_PORT", "8080"),
+                ("MYAPP_LIST", "a, b,c"),
+                ("MYAPP_DB__URI", "postgres://localhost"),
+                ("MYAPP_DB__POOL_SIZE", "4"),
+                ("OTHER_NAME", "ignored"),
+            ]),
+        ))
+        .solidify()?;
+
+    assert_eq!(config.name, "env_name");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.optional, None);
+    assert_eq!(config.list, vec!["a", "b", "c"]);
+    assert_eq!(config.db.uri, "postgres://localhost");
+    assert_eq!(config.db.pool_size, 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_environment_over_file_layer() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(
+            r#"{"name": "json", "port": 1, "optional": "json", "list": [], "db": {"uri": "json", "pool_size": 1}}"#
+                .to_string(),
+            Format::Json,
+        ))
+        .new_layer(Source::EnvironmentFrom(
+            Some("MYAPP_".to_string()),
+            vars(&[("MYAPP_OPTIONAL", "env"), ("MYAPP_DB__POOL_SIZE", "8")]),
+        ))
+        .solidify()?;
+
+    assert_eq!(config.name, "json");
+    assert_eq!(config.optional.as_deref(), Some("env"));
+    assert_eq!(config.db.uri, "json");
+    assert_eq!(config.db.pool_size, 8);
+
+    Ok(())
+}
+
+#[test]
+fn test_environment_parse_error() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::EnvironmentFrom(
+            Some("MYAPP_".to_string()),
+            vars(&[("MYAPP_PORT", "not_a_port")]),
+        ))
+        .solidify();
+
+    match result {
+        Err(Error::EnvironmentError { wrapped }) => {
+            assert!(wrapped.to_string().contains("MYAPP_PORT"));
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct BoundConfig {
+    #[layered(env = "PORT")]
+    #[clap(long)]
+    port: u16,
+    #[layered(env = "APP_NAME")]
+    #[clap(long)]
+    name: Option<String>,
+    #[clap(long)]
+    unbound: Option<String>,
+    #[layered(subconfig)]
+    db: BoundDatabase,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct BoundDatabase {
+    #[layered(env = "DATABASE_URL")]
+    #[clap(long)]
+    uri: String,
+}
+
+#[test]
+fn test_environment_bindings() -> anyhow::Result<()> {
+    let config: BoundConfig = Builder::new()
+        .new_layer(Source::String(
+            r#"{"port": 1, "unbound": "json", "db": {"uri": "json"}}"#.to_string(),
+            Format::Json,
+        ))
+        .new_layer(Source::EnvironmentBindingsFrom(vars(&[
+            ("PORT", "8080"),
+            ("DATABASE_URL", "postgres://localhost"),
+            ("UNBOUND", "ignored"),
+        ])))
+        .solidify()?;
+
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.name, None);
+    assert_eq!(config.unbound.as_deref(), Some("json"));
+    assert_eq!(config.db.uri, "postgres://localhost");
+
+    let config: BoundConfig = Builder::new()
+        .new_layer(Source::EnvironmentBindingsFrom(vars(&[
+            ("PORT", "80"),
+            ("APP_NAME", "bound"),
+            ("DATABASE_URL", "sqlite://"),
+        ])))
+        .solidify()?;
+
+    assert_eq!(config.port, 80);
+    assert_eq!(config.name.as_deref(), Some("bound"));
+    assert_eq!(config.unbound, None);
+    assert_eq!(config.db.uri, "sqlite://");
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct RenamedConfig {
+    #[clap(long)]
+    pool_size: u32,
+    #[serde(rename = "maxConns")]
+    #[clap(long)]
+    max_conns: u32,
+}
+
+#[test]
+fn test_environment_renamed() -> anyhow::Result<()> {
+    let config: RenamedConfig = Builder::new()
+        .new_layer(Source::EnvironmentFrom(
+            Some("MYAPP_".to_string()),
+            vars(&[("MYAPP_POOL_SIZE", "4"), ("MYAPP_MAX_CONNS", "16")]),
+        ))
+        .solidify()?;
+
+    assert_eq!(config.pool_size, 4);
+    assert_eq!(config.max_conns, 16);
+
+    Ok(())
+}
+
+#[test]
+fn test_environment_indexed_list() -> anyhow::Result<()> {
+    let indexed = (0..12)
+        .map(|index| (format!("MYAPP_LIST__{}", index), format!("v{}", index)))
+        .chain([
+            ("MYAPP_NAME".to_string(), "env_name".to_string()),
+            ("MYAPP_PORT".to_string(), "8080".to_string()),
+            ("MYAPP_DB__URI".to_string(), "sqlite://".to_string()),
+            ("MYAPP_DB__POOL_SIZE".to_string(), "1".to_string()),
+        ])
+        .collect::<HashMap<_, _>>();
+
+    let config: Config = Builder::new()
+        .new_layer(Source::EnvironmentFrom(Some("MYAPP_".to_string()), indexed))
+        .solidify()?;
+
+    let expected = (0..12)
+        .map(|index| format!("v{}", index))
+        .collect::<Vec<_>>();
+    assert_eq!(config.list, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_environment_indexed_list_invalid_index() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::EnvironmentFrom(
+            Some("MYAPP_".to_string()),
+            vars(&[("MYAPP_LIST__0", "a"), ("MYAPP_LIST__first", "b")]),
+        ))
+        .solidify();
+
+    match result {
+        Err(Error::EnvironmentError { wrapped }) => {
+            assert!(wrapped.to_string().contains("\"first\""));
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_environment_conflict() {
+    // Setting a subconfig and one of its fields fails, rather than one of them winning
+    // depending on the order of the variables
+    for _ in 0..8 {
+        let result: Result<Config> = Builder::new()
+            .new_layer(Source::EnvironmentFrom(
+                Some("MYAPP_".to_string()),
+                vars(&[("MYAPP_DB", "sqlite://"), ("MYAPP_DB__URI", "postgres://")]),
+            ))
+            .solidify();
+
+        match result {
+            Err(Error::EnvironmentError { wrapped }) => {
+                let message = wrapped.to_string();
+                assert!(message.contains("MYAPP_DB "), "{}", message);
+                assert!(message.contains("MYAPP_DB__URI"), "{}", message);
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
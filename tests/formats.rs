@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{
+    formats::{BoxError, FormatBackend, Visit},
+    Builder, Error, Format, LayeredConf, Result, Source,
+};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+}
+
+fn load_file(path: &str) -> Result<Config> {
+    Builder::new()
+        .new_layer(Source::File(PathBuf::from(path), Format::Auto))
+        .solidify()
+}
+
+fn load_string(string: &str) -> Result<Config> {
+    Builder::new()
+        .new_layer(Source::String(string.to_string(), Format::Auto))
+        .solidify()
+}
+
+#[test]
+fn test_auto_format_extensions() -> anyhow::Result<()> {
+    for name in ["config.yml", "UPPER.JSON", "config.toml.example"] {
+        let config = load_file(&format!("tests/formats/{}", name))?;
+        assert_eq!(config.name, format!("formats/{}", name));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_format_content() -> anyhow::Result<()> {
+    for name in ["no_extension", "config.example"] {
+        let config = load_file(&format!("tests/formats/{}", name))?;
+        assert_eq!(config.name, format!("formats/{}", name));
+    }
+
+    assert_eq!(load_string(r#"{"name": "json"}"#)?.name, "json");
+    assert_eq!(load_string(r#"name = "toml""#)?.name, "toml");
+    assert_eq!(load_string("name: yaml")?.name, "yaml");
+
+    Ok(())
+}
+
+#[test]
+fn test_auto_format_failed() {
+    assert!(matches!(
+        load_file("tests/formats/list.conf"),
+        Err(Error::UnknownExtension { extension: Some(extension) }) if extension == "conf"
+    ));
+    assert!(matches!(
+        load_string("- not\n- a\n- config"),
+        Err(Error::AutoFormatFailed)
+    ));
+}
+
+/// JSON with `//` line comments
+#[derive(Debug)]
+struct JsonComments;
+
+impl FormatBackend for JsonComments {
+    fn name(&self) -> &'static str {
+        "jsonc"
+    }
+
+    fn extensions(&self) -> &[&'static str] {
+        &["jsonc"]
+    }
+
+    fn deserialize(&self, input: &str, visit: &mut Visit) -> std::result::Result<(), BoxError> {
+        let stripped = input
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut deserializer = serde_json::Deserializer::from_str(&stripped);
+        visit(&mut <dyn erased_serde::Deserializer>::erase(
+            &mut deserializer,
+        ))?;
+        deserializer.end()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_custom_format() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .register_format(JsonComments)
+        .new_layer(Source::File(
+            PathBuf::from("tests/formats/config.jsonc"),
+            Format::Auto,
+        ))
+        .solidify()?;
+    assert_eq!(config.name, "formats/config.jsonc");
+
+    let config: Config = Builder::new()
+        .register_format(JsonComments)
+        .new_layer(Source::String(
+            "// Custom\n{\"name\": \"custom\"}".to_string(),
+            Format::Custom("jsonc"),
+        ))
+        .solidify()?;
+    assert_eq!(config.name, "custom");
+
+    let result: Result<Config> = Builder::new()
+        .register_format(JsonComments)
+        .new_layer(Source::String("{".to_string(), Format::Custom("jsonc")))
+        .solidify();
+    match result {
+        Err(Error::At { source, .. }) => {
+            assert!(matches!(*source, Error::FormatError { format, .. } if format == "jsonc"))
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String("{}".to_string(), Format::Custom("jsonc")))
+        .solidify();
+    assert!(matches!(result, Err(Error::UnknownFormat { name }) if name == "jsonc"));
+
+    Ok(())
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn test_ron() -> anyhow::Result<()> {
+    let config = load_file("tests/formats/config.ron")?;
+    assert_eq!(config.name, "formats/config.ron");
+
+    Ok(())
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn test_json5() -> anyhow::Result<()> {
+    let config = load_file("tests/formats/config.json5")?;
+    assert_eq!(config.name, "formats/config.json5");
+
+    Ok(())
+}
+
+#[cfg(feature = "ini")]
+#[test]
+fn test_ini() -> anyhow::Result<()> {
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    struct Config {
+        #[clap(long)]
+        name: String,
+        #[layered(subconfig)]
+        sub: SubConfig,
+    }
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(subconfig)]
+    struct SubConfig {
+        #[clap(long)]
+        number: u32,
+    }
+
+    let config: Config = Builder::new()
+        .new_layer(Source::File(
+            PathBuf::from("tests/formats/config.ini"),
+            Format::Auto,
+        ))
+        .solidify()?;
+    assert_eq!(config.name, "formats/config.ini");
+    assert_eq!(config.sub.number, 42);
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Error, Format, LayeredConf, MissingField, Source};
+
+trait Backend: Clone + Debug {
+    fn kind() -> &'static str;
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct ServiceConfig<B: Backend> {
+    #[clap(long)]
+    name: String,
+    #[layered(default = "default_port")]
+    #[clap(long)]
+    port: u16,
+    #[layered(subconfig)]
+    backend: B,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Postgres {
+    #[clap(long)]
+    url: String,
+    #[layered(default = "default_pool_size")]
+    #[clap(long)]
+    pool_size: u32,
+}
+
+fn default_pool_size() -> u32 {
+    4
+}
+
+impl Backend for Postgres {
+    fn kind() -> &'static str {
+        "postgres"
+    }
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Redis {
+    #[clap(long)]
+    host: String,
+    #[clap(long)]
+    db: Option<u8>,
+}
+
+impl Backend for Redis {
+    fn kind() -> &'static str {
+        "redis"
+    }
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Limits<T>
+where
+    T: Clone + Debug + PartialEq + std::str::FromStr + Send + Sync + 'static,
+    <T as std::str::FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    #[clap(long)]
+    max: T,
+    #[clap(long)]
+    min: Option<T>,
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_generic_subconfig() -> anyhow::Result<()> {
+    let config: ServiceConfig<Postgres> = Builder::new()
+        .new_layer(Source::String(
+            "backend:\n  url: postgres://lower\n  pool_size: 2\n".to_string(),
+            Format::Yaml,
+        ))
+        .new_layer(Source::String(
+            "{\"port\": 9000, \"_unset\": [\"backend.pool_size\"]}".to_string(),
+            Format::Json,
+        ))
+        .new_layer(Source::String(
+            "name = \"users\"\n\n[backend]\nurl = \"postgres://db\"\n".to_string(),
+            Format::Toml,
+        ))
+        .solidify()?;
+    assert_eq!(config.name, "users");
+    assert_eq!(config.port, 9000);
+    assert_eq!(config.backend.url, "postgres://db");
+    assert_eq!(config.backend.pool_size, 4);
+    assert_eq!(Postgres::kind(), "postgres");
+
+    let config: ServiceConfig<Redis> = Builder::new()
+        .new_layer(Source::ArgumentsFrom(args(&[
+            "service",
+            "--name",
+            "cache",
+            "--host",
+            "localhost",
+        ])))
+        .solidify()?;
+    assert_eq!(config.name, "cache");
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.backend.host, "localhost");
+    assert_eq!(config.backend.db, None);
+    assert_eq!(Redis::kind(), "redis");
+
+    Ok(())
+}
+
+#[test]
+fn test_generic_subconfig_missing() {
+    let result = Builder::<ServiceConfig<Redis>>::new()
+        .new_layer(Source::String(
+            "name = \"cache\"\n".to_string(),
+            Format::Toml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::SolidifyFailedMissing { missing }) => assert_eq!(
+            missing,
+            vec![MissingField {
+                path: "backend.host".to_string(),
+                flag: Some("--host".to_string()),
+            }]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_generic_field() -> anyhow::Result<()> {
+    let limits: Limits<f64> = Builder::new()
+        .new_layer(Source::String(
+            "max: 1.0\nmin: 0.5\n".to_string(),
+            Format::Yaml,
+        ))
+        .new_layer(Source::ArgumentsFrom(args(&["limits", "--max", "2.5"])))
+        .solidify()?;
+    assert_eq!(limits.max, 2.5);
+    assert_eq!(limits.min, Some(0.5));
+
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Error, Format, LayeredConf, Location, Result, Source};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[layered(load_config)]
+    #[clap(long)]
+    config: Option<std::path::PathBuf>,
+    #[layered(range(min = 1))]
+    #[clap(long)]
+    port: u16,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[layered(non_empty)]
+    #[clap(long)]
+    uri: String,
+}
+
+fn parse_error_location(string: &str, format: Format) -> Option<Location> {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(string.to_string(), format))
+        .solidify();
+
+    match result {
+        Err(Error::At { location, source }) => {
+            assert!(matches!(
+                *source,
+                Error::JsonError { .. } | Error::TomlError { .. } | Error::YamlError { .. }
+            ));
+            Some(*location)
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_error_location() {
+    let lines = |location: Option<Location>| {
+        let location = location.unwrap();
+        assert!(matches!(location.origin, Source::String(..)));
+        (location.line, location.column)
+    };
+
+    assert_eq!(
+        lines(parse_error_location(
+            "{\n  \"port\": \"eighty\"\n}",
+            Format::Json
+        )),
+        (Some(2), Some(18))
+    );
+    assert_eq!(
+        lines(parse_error_location("port = 80\nport 81\n", Format::Toml)),
+        (Some(2), Some(6))
+    );
+    assert_eq!(
+        lines(parse_error_location("port: 80\ndb: uri: x\n", Format::Yaml)),
+        (Some(2), Some(8))
+    );
+}
+
+#[test]
+fn test_parse_error_file() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.yaml");
+    fs::write(&path, "port: 80\ndb:\n  uri: [not, a, string]\n")?;
+
+    let error = Builder::<Config>::new()
+        .new_layer(Source::File(path.clone(), Format::Auto))
+        .solidify()
+        .unwrap_err();
+
+    let path = path.canonicalize()?;
+    match &error {
+        Error::At { location, source } if matches!(**source, Error::YamlError { .. }) => {
+            assert_eq!(location.origin, Source::File(path.clone(), Format::Yaml));
+            assert_eq!(location.line, Some(3));
+        }
+        other => panic!("Unexpected error {:?}", other),
+    }
+    assert!(error
+        .to_string()
+        .ends_with(&format!(" at {}:3:8", path.display())));
+
+    Ok(())
+}
+
+#[test]
+fn test_validation_location() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let base = dir.path().join("base.toml");
+    fs::write(&base, "port = 0\n\n[db]\nuri = \"\"\n")?;
+    let main = dir.path().join("main.yaml");
+    fs::write(&main, "config: base.toml\ndb:\n  uri: \"\"\n")?;
+
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::File(main.clone(), Format::Auto))
+        .solidify();
+
+    let failures = match result {
+        Err(Error::ValidationFailed { failures }) => failures,
+        other => panic!("Unexpected result {:?}", other),
+    };
+    assert_eq!(
+        failures
+            .iter()
+            .map(|failure| failure.to_string())
+            .collect::<Vec<_>>(),
+        vec![
+            format!(
+                "port: must be at least 1, got 0 at {}:1:1",
+                base.canonicalize()?.display()
+            ),
+            format!(
+                "db.uri: must not be empty at {}:3:3",
+                main.canonicalize()?.display()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_validation_location_without_lines() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::EnvironmentFrom(
+            Some("APP_".to_string()),
+            [("APP_PORT", "0"), ("APP_DB__URI", "db")]
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        ))
+        .solidify();
+
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(
+                failures[0].to_string(),
+                "port: must be at least 1, got 0 at <environment>"
+            );
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+fn validation_failures(string: &str, format: Format) -> Vec<String> {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(string.to_string(), format))
+        .solidify();
+
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            failures.iter().map(|failure| failure.to_string()).collect()
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_validation_location_nested_keys() {
+    // The `uri` of `db` is found after the `uri` of a table within it
+    assert_eq!(
+        validation_failures(
+            r#"{"port": 1, "db": {"pool": {"uri": "x"}, "uri": ""}}"#,
+            Format::Json
+        ),
+        vec!["db.uri: must not be empty at <string>:1:43"]
+    );
+    assert_eq!(
+        validation_failures(
+            "port: 1\ndb:\n  pool:\n    uri: x\n  uri: \"\"\n",
+            Format::Yaml
+        ),
+        vec!["db.uri: must not be empty at <string>:5:3"]
+    );
+    assert_eq!(
+        validation_failures(
+            "port = 1\n\n[db.pool]\nuri = \"x\"\n\n[db]\nuri = \"\"\n",
+            Format::Toml
+        ),
+        vec!["db.uri: must not be empty at <string>:7:1"]
+    );
+
+    // A key that's in a sibling table isn't attributed to the field
+    assert_eq!(
+        validation_failures("cache:\n  port: 0\ndb:\n  uri: db\nport: 0\n", Format::Yaml),
+        vec!["port: must be at least 1, got 0 at <string>:5:1"]
+    );
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Renamed {
+    #[layered(range(min = 1))]
+    #[clap(long)]
+    pool_size: u32,
+    #[layered(subconfig)]
+    #[serde(rename = "db")]
+    database: Database,
+}
+
+#[test]
+fn test_validation_location_renamed() {
+    let result: Result<Renamed> = Builder::new()
+        .new_layer(Source::String(
+            "db:\n  uri: \"\"\npool-size: 0\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+
+    match result {
+        Err(Error::ValidationFailed { failures }) => assert_eq!(
+            failures
+                .iter()
+                .map(|failure| failure.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "pool-size: must be at least 1, got 0 at <string>:3:1",
+                "db.uri: must not be empty at <string>:2:3",
+            ]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_validation_location_comments_and_strings() {
+    // Keys in comments and string values aren't taken for the field's key
+    assert_eq!(
+        validation_failures(
+            "# port: 0\ndb:\n  uri: \"port: 0\"\nport: 0\n",
+            Format::Yaml
+        ),
+        vec!["port: must be at least 1, got 0 at <string>:4:1"]
+    );
+    assert_eq!(
+        validation_failures(
+            "# port = 0\nnote = \"port = 0\"\nport = 0\n\n[db]\nuri = \"x\"\n",
+            Format::Toml
+        ),
+        vec!["port: must be at least 1, got 0 at <string>:3:1"]
+    );
+    assert_eq!(
+        validation_failures(
+            r#"{"note": "\"port\": 0", "db": {"uri": "x"}, "port": 0}"#,
+            Format::Json
+        ),
+        vec!["port: must be at least 1, got 0 at <string>:1:46"]
+    );
+}
+
+#[test]
+fn test_validation_location_flow_and_inline_tables() {
+    assert_eq!(
+        validation_failures("{port: 1, db: {uri: ''}}", Format::Yaml),
+        vec!["db.uri: must not be empty at <string>:1:16"]
+    );
+    assert_eq!(
+        validation_failures("port = 1\ndb = { uri = \"\" }\n", Format::Toml),
+        vec!["db.uri: must not be empty at <string>:2:8"]
+    );
+    assert_eq!(
+        validation_failures("port = 1\ndb.uri = \"\"\n", Format::Toml),
+        vec!["db.uri: must not be empty at <string>:2:4"]
+    );
+}
+
+#[test]
+fn test_location_after_non_ascii() {
+    // JSON's parser counts columns in bytes, TOML's and YAML's in chars, and locations in chars
+    assert_eq!(
+        validation_failures(
+            r#"{"note": "éééé", "db": {"uri": "x"}, "port": 0}"#,
+            Format::Json
+        ),
+        vec!["port: must be at least 1, got 0 at <string>:1:39"]
+    );
+    assert_eq!(
+        validation_failures("{note: éééé, db: {uri: x}, port: 0}", Format::Yaml),
+        vec!["port: must be at least 1, got 0 at <string>:1:28"]
+    );
+    assert_eq!(
+        validation_failures(
+            "port = 1\ndb = { note = \"éééé\", uri = \"\" }\n",
+            Format::Toml
+        ),
+        vec!["db.uri: must not be empty at <string>:2:23"]
+    );
+
+    let column = |string: &str, format| parse_error_location(string, format).unwrap().column;
+    assert_eq!(
+        column(r#"{"note": "éééé", "port": "eighty"}"#, Format::Json),
+        Some(33)
+    );
+    assert_eq!(column("note = \"éééé\" port = 0\n", Format::Toml), Some(15));
+    assert_eq!(column("note: éééé\ndb: uri: x\n", Format::Yaml), Some(8));
+    assert_eq!(column("{note: éééé, port: [}", Format::Yaml), Some(21));
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Error, Format, LayeredConf, MissingField, Source};
+
+mod db {
+    use serde::{Deserialize, Serialize};
+
+    use layeredconf::LayeredConf;
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(subconfig)]
+    pub struct DbConfig {
+        #[clap(long)]
+        pub url: String,
+        #[clap(long)]
+        pub pool_size: std::option::Option<u32>,
+    }
+}
+
+mod cache {
+    pub mod redis {
+        use serde::{Deserialize, Serialize};
+
+        use layeredconf::LayeredConf;
+
+        #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+        #[layered(subconfig)]
+        pub struct RedisConfig {
+            #[clap(long)]
+            pub host: String,
+        }
+    }
+
+    pub use redis::RedisConfig as Config;
+}
+
+type Cache = cache::Config;
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: ::std::option::Option<String>,
+    #[layered(subconfig)]
+    db: crate::db::DbConfig,
+    #[layered(subconfig)]
+    cache: Cache,
+}
+
+#[test]
+fn test_subconfig_paths() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(
+            "db:\n  url: postgres://db\ncache:\n  host: localhost\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.name, None);
+    assert_eq!(config.db.url, "postgres://db");
+    assert_eq!(config.db.pool_size, None);
+    assert_eq!(config.cache.host, "localhost");
+
+    let result = Builder::<Config>::new()
+        .new_layer(Source::String(
+            "{\"db\": {\"pool_size\": 4}}".to_string(),
+            Format::Json,
+        ))
+        .solidify();
+    match result {
+        Err(Error::SolidifyFailedMissing { missing }) => assert_eq!(
+            missing,
+            vec![
+                MissingField {
+                    path: "db.url".to_string(),
+                    flag: Some("--url".to_string()),
+                },
+                MissingField {
+                    path: "cache.host".to_string(),
+                    flag: Some("--host".to_string()),
+                },
+            ]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{
+    Builder, Error, Format, LayeredConf, LayeredConfDiff, MissingField, Result, Source,
+};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+    #[layered(subconfig)]
+    tls: Option<TlsConfig>,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct TlsConfig {
+    #[layered(non_empty)]
+    #[clap(long)]
+    cert: String,
+    #[clap(long)]
+    key: String,
+    #[layered(default = "default_min_version")]
+    #[clap(long)]
+    min_version: String,
+}
+
+fn default_min_version() -> String {
+    "1.2".to_string()
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_untouched() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String("name: test".to_string(), Format::Yaml))
+        .solidify()?;
+    assert!(config.tls.is_none());
+
+    let dumped = Builder::<Config>::new()
+        .new_layer(Source::String("name: test".to_string(), Format::Yaml))
+        .dump(Format::Json, true)?;
+    assert_eq!(dumped, "{\n  \"name\": \"test\"\n}");
+
+    Ok(())
+}
+
+#[test]
+fn test_touched() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(
+            "name: test\ntls:\n  cert: cert.pem\n".to_string(),
+            Format::Yaml,
+        ))
+        .new_layer(Source::ArgumentsFrom(args(&["app", "--key", "key.pem"])))
+        .solidify()?;
+    let tls = config.tls.unwrap();
+    assert_eq!(tls.cert, "cert.pem");
+    assert_eq!(tls.key, "key.pem");
+    assert_eq!(tls.min_version, "1.2");
+
+    Ok(())
+}
+
+#[test]
+fn test_present() -> anyhow::Result<()> {
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    struct Defaulted {
+        #[layered(subconfig)]
+        tls: Option<DefaultedTls>,
+    }
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(subconfig)]
+    struct DefaultedTls {
+        #[layered(default = "default_min_version")]
+        min_version: String,
+    }
+
+    let config: Defaulted = Builder::new()
+        .new_layer(Source::String("tls: {}".to_string(), Format::Yaml))
+        .new_layer(Source::String("{}".to_string(), Format::Yaml))
+        .solidify()?;
+    assert_eq!(config.tls.unwrap().min_version, "1.2");
+
+    let dumped = Builder::<Defaulted>::new()
+        .new_layer(Source::String("tls: {}".to_string(), Format::Yaml))
+        .dump(Format::Json, false)?;
+    assert_eq!(dumped, "{\n  \"tls\": {}\n}");
+
+    let config: Defaulted = Builder::new()
+        .new_layer(Source::String("tls: {}".to_string(), Format::Yaml))
+        .new_layer(Source::String("_unset: [tls]".to_string(), Format::Yaml))
+        .solidify()?;
+    assert!(config.tls.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_defaults() -> anyhow::Result<()> {
+    let dumped = Builder::<Config>::new()
+        .new_layer(Source::String(
+            "name: test\ntls:\n  cert: cert.pem\n  key: key.pem\n".to_string(),
+            Format::Yaml,
+        ))
+        .dump(Format::Json, true)?;
+    assert_eq!(
+        dumped,
+        "{\n  \"name\": \"test\",\n  \"tls\": {\n    \"cert\": \"cert.pem\",\n    \"key\": \"key.pem\",\n    \"min_version\": \"1.2\"\n  }\n}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_touched_missing() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            "{\"name\": \"test\", \"tls\": {\"min_version\": \"1.3\"}}".to_string(),
+            Format::Json,
+        ))
+        .solidify();
+    match result {
+        Err(Error::SolidifyFailedMissing { missing }) => assert_eq!(
+            missing,
+            vec![
+                MissingField {
+                    path: "tls.cert".to_string(),
+                    flag: Some("--cert".to_string()),
+                },
+                MissingField {
+                    path: "tls.key".to_string(),
+                    flag: Some("--key".to_string()),
+                },
+            ]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_unset() -> anyhow::Result<()> {
+    let config: Config = Builder::new()
+        .new_layer(Source::String(
+            "name: test\ntls:\n  cert: cert.pem\n  key: key.pem\n".to_string(),
+            Format::Yaml,
+        ))
+        .new_layer(Source::String("_unset: [tls]".to_string(), Format::Yaml))
+        .solidify()?;
+    assert!(config.tls.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_validate() {
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            "name = \"test\"\n\n[tls]\ncert = \"\"\nkey = \"key.pem\"\n".to_string(),
+            Format::Toml,
+        ))
+        .solidify();
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].path, "tls.cert");
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_diff() {
+    let tls = TlsConfig {
+        cert: "cert.pem".to_string(),
+        key: "key.pem".to_string(),
+        min_version: "1.2".to_string(),
+    };
+    let old = Config {
+        name: "test".to_string(),
+        tls: None,
+    };
+    let new = Config {
+        name: "test".to_string(),
+        tls: Some(tls.clone()),
+    };
+    let newer = Config {
+        name: "test".to_string(),
+        tls: Some(TlsConfig {
+            min_version: "1.3".to_string(),
+            ..tls
+        }),
+    };
+
+    let changes = old.diff(&new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].path, "tls");
+    assert_eq!(changes[0].old, None);
+
+    let changes = new.diff(&newer);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(
+        changes[0].to_string(),
+        "tls.min_version: \"1.2\" -> \"1.3\""
+    );
+}
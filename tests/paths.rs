@@ -19,7 +19,7 @@ fn test() -> anyhow::Result<()> {
     let arg_config = current_dir.join("tests/paths/arg/config.yaml");
     let arg_str = arg_config.to_str().unwrap();
 
-    let args: Vec<String> = vec!["paths", "--config", arg_str]
+    let args: Vec<String> = ["paths", "--config", arg_str]
         .iter()
         .map(|s| s.to_string())
         .collect();
@@ -0,0 +1,230 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Format, LayeredConf, Source, ValueSource};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[layered(load_config)]
+    #[clap(long)]
+    config: Option<PathBuf>,
+    #[clap(long)]
+    name: String,
+    #[layered(default)]
+    #[clap(long)]
+    port: u16,
+    #[clap(long)]
+    optional: Option<String>,
+    #[layered(merge = "append")]
+    #[clap(long)]
+    plugins: Option<Vec<String>>,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[clap(long)]
+    uri: String,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Proxy {
+    #[layered(default)]
+    #[clap(long)]
+    pool_size: u32,
+    #[layered(subconfig)]
+    upstreams: HashMap<String, Upstream>,
+    #[layered(subconfig)]
+    tls: Option<Tls>,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+#[serde(rename_all = "camelCase")]
+struct Upstream {
+    #[clap(long)]
+    url: String,
+    #[layered(default)]
+    #[clap(long)]
+    max_weight: u32,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Tls {
+    #[clap(long)]
+    cert: String,
+    #[layered(default)]
+    #[clap(long)]
+    port: u16,
+}
+
+#[test]
+fn test_provenance() -> anyhow::Result<()> {
+    let lowest = Source::String(
+        r#"{"name": "lowest", "db": {"uri": "lowest"}}"#.to_string(),
+        Format::Json,
+    );
+    let highest = Source::String(
+        r#"{"config": "tests/paths/subdir/config.yaml", "port": 80}"#.to_string(),
+        Format::Json,
+    );
+    let args = Source::ArgumentsFrom(vec![
+        "provenance".to_string(),
+        "--uri".to_string(),
+        "arg".to_string(),
+    ]);
+
+    let (config, provenance) = Builder::<Config>::new()
+        .new_layer(lowest)
+        .new_layer(highest.clone())
+        .new_layer(args.clone())
+        .solidify_with_provenance()?;
+
+    assert_eq!(config.name, "paths/subdir/config.yaml");
+    assert_eq!(config.port, 80);
+    assert_eq!(config.db.uri, "arg");
+
+    assert_eq!(
+        provenance.get("name"),
+        Some(&ValueSource::Layer {
+            source: Source::File(
+                PathBuf::from("tests/paths/subdir/config.yaml"),
+                Format::Auto
+            ),
+            parents: vec![highest.clone()],
+        })
+    );
+    assert_eq!(
+        provenance.get("config"),
+        Some(&ValueSource::Layer {
+            source: highest.clone(),
+            parents: vec![],
+        })
+    );
+    assert_eq!(
+        provenance.get("port"),
+        Some(&ValueSource::Layer {
+            source: highest,
+            parents: vec![],
+        })
+    );
+    assert_eq!(
+        provenance.get("db.uri"),
+        Some(&ValueSource::Layer {
+            source: args,
+            parents: vec![],
+        })
+    );
+    assert_eq!(provenance.get("optional"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_provenance_defaults() -> anyhow::Result<()> {
+    let (config, provenance) = Builder::<Config>::new()
+        .new_layer(Source::String(
+            "name: yaml\ndb:\n  uri: yaml".to_string(),
+            Format::Yaml,
+        ))
+        .solidify_with_provenance()?;
+
+    assert_eq!(config.port, 0);
+    assert_eq!(provenance.get("port"), Some(&ValueSource::Default));
+    assert_eq!(
+        provenance
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<Vec<_>>(),
+        vec!["db.uri", "name", "port"],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_provenance_merged() -> anyhow::Result<()> {
+    let lower = Source::String(
+        "name: lower\nplugins: [a]\ndb:\n  uri: lower".to_string(),
+        Format::Yaml,
+    );
+    let higher = Source::String("plugins: [b]".to_string(), Format::Yaml);
+
+    let (config, provenance) = Builder::<Config>::new()
+        .new_layer(lower.clone())
+        .new_layer(higher.clone())
+        .solidify_with_provenance()?;
+
+    assert_eq!(config.plugins, Some(vec!["a".to_string(), "b".to_string()]));
+    assert_eq!(
+        provenance.get("plugins"),
+        Some(&ValueSource::Merged(vec![
+            ValueSource::Layer {
+                source: higher,
+                parents: vec![],
+            },
+            ValueSource::Layer {
+                source: lower,
+                parents: vec![],
+            },
+        ]))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_provenance_unset() -> anyhow::Result<()> {
+    let lower = Source::String(
+        "name: lower\nport: 80\noptional: lower\ndb:\n  uri: lower".to_string(),
+        Format::Yaml,
+    );
+    let higher = Source::String("_unset: [port, optional]".to_string(), Format::Yaml);
+
+    let (config, provenance) = Builder::<Config>::new()
+        .new_layer(lower)
+        .new_layer(higher)
+        .solidify_with_provenance()?;
+
+    assert_eq!(config.port, 0);
+    assert_eq!(config.optional, None);
+    assert_eq!(provenance.get("port"), Some(&ValueSource::Default));
+    assert_eq!(provenance.get("optional"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_provenance_filled_defaults() -> anyhow::Result<()> {
+    let source = Source::String(
+        "upstreams:\n  a:\n    url: http://a\ntls:\n  cert: cert.pem".to_string(),
+        Format::Yaml,
+    );
+
+    let (config, provenance) = Builder::<Proxy>::new()
+        .new_layer(source.clone())
+        .solidify_with_provenance()?;
+
+    assert_eq!(config.upstreams["a"].max_weight, 0);
+    assert_eq!(config.tls.map(|tls| tls.port), Some(0));
+    assert_eq!(
+        provenance
+            .iter()
+            .map(|(path, source)| (path.as_str(), source == &ValueSource::Default))
+            .collect::<Vec<_>>(),
+        vec![
+            ("poolSize", true),
+            ("tls.cert", false),
+            ("tls.port", true),
+            ("upstreams.a.maxWeight", true),
+            ("upstreams.a.url", false),
+        ],
+    );
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use layeredconf::{LayeredConf, LayeredConfSchema};
+
+/// My application
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    /// Further config file to load
+    #[layered(load_config)]
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// Name of the instance
+    #[clap(long)]
+    name: String,
+    /// Port to listen on
+    #[layered(default = "default_port")]
+    #[clap(long)]
+    port: u16,
+    #[layered(default)]
+    #[clap(long)]
+    verbose: bool,
+    #[clap(long)]
+    ratio: Option<f64>,
+    #[clap(long)]
+    plugins: Vec<String>,
+    #[clap(skip)]
+    labels: HashMap<String, u32>,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+/// Database connection
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug, Default)]
+#[layered(subconfig, default)]
+struct Database {
+    /// Connection URI
+    #[clap(long)]
+    uri: String,
+    #[clap(long)]
+    pool_size: u32,
+}
+
+#[test]
+fn test_json_schema() {
+    assert_eq!(
+        Config::json_schema().unwrap(),
+        json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": "Config",
+            "description": "My application",
+            "type": "object",
+            "properties": {
+                "config": {
+                    "description": "Further config file to load",
+                    "type": "string",
+                },
+                "name": {
+                    "description": "Name of the instance",
+                    "type": "string",
+                },
+                "port": {
+                    "description": "Port to listen on",
+                    "type": "integer",
+                    "default": 8080,
+                },
+                "verbose": {
+                    "type": "boolean",
+                    "default": false,
+                },
+                "ratio": {
+                    "type": "number",
+                },
+                "plugins": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                },
+                "labels": {
+                    "type": "object",
+                    "additionalProperties": { "type": "integer" },
+                },
+                "db": {
+                    "description": "Database connection",
+                    "type": "object",
+                    "properties": {
+                        "uri": {
+                            "description": "Connection URI",
+                            "type": "string",
+                            "default": "",
+                        },
+                        "pool_size": {
+                            "type": "integer",
+                            "default": 0,
+                        },
+                    },
+                },
+            },
+        })
+    );
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Renamed {
+    #[layered(default)]
+    #[clap(long)]
+    pool_size: u32,
+    #[serde(rename = "URI")]
+    #[clap(long)]
+    uri: String,
+}
+
+#[test]
+fn test_json_schema_renamed() {
+    let schema = Renamed::json_schema().unwrap();
+    assert_eq!(
+        schema["properties"],
+        json!({
+            "pool-size": {
+                "type": "integer",
+                "default": 0,
+            },
+            "URI": {
+                "type": "string",
+            },
+        })
+    );
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Server {
+    #[layered(subconfig)]
+    tls: Option<Tls>,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Tls {
+    #[clap(long)]
+    cert: String,
+    #[layered(default = "default_tls_port")]
+    #[clap(long)]
+    port: u16,
+}
+
+fn default_tls_port() -> u16 {
+    8443
+}
+
+#[test]
+fn test_json_schema_optional_subconfig() {
+    let schema = Server::json_schema().unwrap();
+    assert_eq!(
+        schema["properties"]["tls"]["properties"],
+        json!({
+            "cert": {
+                "type": "string",
+            },
+            "port": {
+                "type": "integer",
+                "default": 8443,
+            },
+        })
+    );
+}
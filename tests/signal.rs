@@ -0,0 +1,42 @@
+use std::{fs, sync::mpsc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use signal_hook::{consts::SIGHUP, low_level::raise};
+
+use layeredconf::{Builder, Format, LayeredConf, Result, Source};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[clap(long)]
+    name: String,
+}
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn test_reload_on_sighup() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.yaml");
+    fs::write(&path, "name: first")?;
+
+    let (sender, receiver) = mpsc::channel::<Result<_>>();
+    let reload = Builder::<Config>::new()
+        .new_layer(Source::File(path.clone(), Format::Auto))
+        .reload_on_sighup(move |result| sender.send(result).unwrap())?;
+
+    let shared = reload.shared();
+    assert_eq!(shared.load().name, "first");
+
+    fs::write(&path, "name: second")?;
+    raise(SIGHUP)?;
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.name, "second");
+    assert_eq!(shared.load().name, "second");
+    assert_eq!(reload.current().name, "second");
+
+    fs::write(&path, "name: [not, a, string]")?;
+    raise(SIGHUP)?;
+    assert!(receiver.recv_timeout(TIMEOUT)?.is_err());
+    assert_eq!(shared.load().name, "second");
+
+    Ok(())
+}
@@ -0,0 +1,285 @@
+use std::{
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Error, Format, LayeredConf, Result, Source, UnknownKey};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[layered(load_config)]
+    #[clap(long)]
+    config: Option<std::path::PathBuf>,
+    #[clap(long)]
+    name: Option<String>,
+    #[layered(subconfig)]
+    database: Database,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[clap(long)]
+    uri: Option<String>,
+    #[clap(long)]
+    pool_size: Option<u32>,
+}
+
+fn unknown_keys(result: Result<Config>) -> Vec<String> {
+    match result {
+        Err(Error::UnknownKeys { unknown }) => unknown.iter().map(UnknownKey::to_string).collect(),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict() {
+    let yaml = "name: test\ndatabse:\n  uri: db\ndatabase:\n  pool_sise: 4\n  timeout: 10\n";
+
+    let result = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(yaml.to_string(), Format::Yaml))
+        .solidify();
+    assert_eq!(
+        unknown_keys(result),
+        vec![
+            "databse (did you mean database?) at <string>:2:1",
+            "database.pool_sise (did you mean pool_size?) at <string>:5:3",
+            "database.timeout at <string>:6:3",
+        ]
+    );
+
+    // Layers aren't strict by default
+    let config: Config = Builder::new()
+        .new_layer(Source::String(yaml.to_string(), Format::Yaml))
+        .solidify()
+        .unwrap();
+    assert_eq!(config.name.as_deref(), Some("test"));
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Renamed {
+    #[clap(long)]
+    listen_port: Option<u16>,
+    #[layered(subconfig)]
+    #[serde(rename = "db")]
+    database: RenamedDatabase,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+#[serde(rename_all = "camelCase")]
+struct RenamedDatabase {
+    #[clap(long)]
+    pool_size: Option<u32>,
+}
+
+#[test]
+fn test_strict_renamed() {
+    let yaml = "listen-port: 80
+db:
+  poolSize: 4
+";
+    let config: Renamed = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(yaml.to_string(), Format::Yaml))
+        .solidify()
+        .unwrap();
+    assert_eq!(config.listen_port, Some(80));
+    assert_eq!(config.database.pool_size, Some(4));
+
+    let yaml = "listen_port: 80
+database:
+  poolSise: 4
+db:
+  pool_size: 4
+";
+    let result: Result<Renamed> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(yaml.to_string(), Format::Yaml))
+        .solidify();
+    match result {
+        Err(Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "listen_port (did you mean listen-port?) at <string>:1:1",
+                "database at <string>:2:1",
+                "db.pool_size (did you mean poolSize?) at <string>:5:3",
+            ]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_strict_layer() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.toml");
+    fs::write(&path, "name = \"test\"\n\n[database]\nurl = \"db\"\n")?;
+
+    let result = Builder::new()
+        .new_layer(Source::String("nmae: lenient".to_string(), Format::Yaml))
+        .new_strict_layer(Source::File(path.clone(), Format::Auto))
+        .solidify();
+    assert_eq!(
+        unknown_keys(result),
+        vec![format!(
+            "database.url (did you mean uri?) at {}:4:1",
+            path.canonicalize()?.display()
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_load_config() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("loaded.json");
+    fs::write(&path, "{\"database\": {\"uri\": \"db\", \"pool\": 4}}")?;
+
+    let result = Builder::new()
+        .new_strict_layer(Source::String(
+            format!("config: {}", path.display()),
+            Format::Yaml,
+        ))
+        .solidify();
+    assert_eq!(
+        unknown_keys(result),
+        vec![format!(
+            "database.pool at {}:1:29",
+            path.canonicalize()?.display()
+        )]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_on_unknown_key() -> anyhow::Result<()> {
+    let warnings = Arc::new(Mutex::new(vec![]));
+    let on_unknown_key = warnings.clone();
+
+    let config: Config = Builder::new()
+        .on_unknown_key(move |key| on_unknown_key.lock().unwrap().push(key.clone()))
+        .new_layer(Source::String(
+            "{\"name\": \"test\", \"nam\": \"typo\", \"_unset\": [\"database.uri\"]}".to_string(),
+            Format::Json,
+        ))
+        .solidify()?;
+    assert_eq!(config.name.as_deref(), Some("test"));
+
+    let warnings = warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].path, "nam");
+    assert_eq!(warnings[0].suggestion.as_deref(), Some("name"));
+    assert_eq!(warnings[0].location.as_ref().unwrap().column, Some(19));
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Aliased {
+    #[serde(alias = "database_url", alias = "db_url")]
+    #[clap(long)]
+    url: Option<String>,
+    #[clap(long)]
+    name: Option<String>,
+}
+
+#[test]
+fn test_strict_aliased() {
+    for yaml in ["url: db", "database_url: db", "db_url: db"] {
+        let config: Aliased = Builder::new()
+            .strict(true)
+            .new_layer(Source::String(yaml.to_string(), Format::Yaml))
+            .solidify()
+            .unwrap();
+        assert_eq!(config.url.as_deref(), Some("db"));
+    }
+
+    let result: Result<Aliased> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String("database_uri: db".to_string(), Format::Yaml))
+        .solidify();
+    match result {
+        Err(Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            vec!["database_uri (did you mean database_url?) at <string>:1:1"]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Flattened {
+    #[clap(long)]
+    name: Option<String>,
+    #[serde(flatten)]
+    #[clap(skip)]
+    extra: Option<std::collections::HashMap<String, String>>,
+}
+
+#[test]
+fn test_strict_flattened() {
+    let config: Flattened = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(
+            "name: test\nlabel: extra\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()
+        .unwrap();
+    assert_eq!(config.name.as_deref(), Some("test"));
+    assert_eq!(
+        config.extra.unwrap().get("label").map(String::as_str),
+        Some("extra")
+    );
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct FlattenedSubconfig {
+    #[clap(long)]
+    name: Option<String>,
+    #[layered(subconfig)]
+    #[serde(flatten)]
+    database: Database,
+}
+
+#[test]
+fn test_strict_flattened_subconfig() {
+    let yaml = "name: test\nuri: db\npool_sise: 4\n";
+    let result: Result<FlattenedSubconfig> = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(yaml.to_string(), Format::Yaml))
+        .solidify();
+    match result {
+        Err(Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(UnknownKey::to_string)
+                .collect::<Vec<_>>(),
+            vec!["pool_sise (did you mean pool_size?) at <string>:3:1"]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let config: FlattenedSubconfig = Builder::new()
+        .strict(true)
+        .new_layer(Source::String(
+            "name: test\nuri: db\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()
+        .unwrap();
+    assert_eq!(config.database.uri.as_deref(), Some("db"));
+}
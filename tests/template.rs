@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Format, LayeredConf, LayeredConfTemplate, Source};
+
+/// My application
+///
+/// Serves things.
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    /// Name of the instance
+    #[clap(long)]
+    name: String,
+    /// Port to listen on
+    #[layered(default = "default_port")]
+    #[clap(long)]
+    port: u16,
+    #[clap(long)]
+    optional: Option<String>,
+    #[layered(default)]
+    #[clap(long)]
+    plugins: Vec<String>,
+    #[layered(default = "default_labels")]
+    #[clap(skip)]
+    labels: HashMap<String, String>,
+    #[layered(subconfig)]
+    db: Database,
+    /// Cache settings
+    #[layered(subconfig)]
+    cache: Cache,
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+fn default_labels() -> HashMap<String, String> {
+    HashMap::from([("env".to_string(), "dev \"local\"".to_string())])
+}
+
+/// Database connection
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    /// Connection URI
+    #[clap(long)]
+    uri: String,
+    #[layered(subconfig)]
+    pool: Pool,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Pool {
+    #[layered(default)]
+    #[clap(long)]
+    size: u32,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Cache {
+    #[clap(long)]
+    ttl: Option<u64>,
+}
+
+static YAML: &str = r#"# My application
+#
+# Serves things.
+
+# Name of the instance
+# Required
+# name:
+
+# Port to listen on
+port: 8080
+
+# optional:
+
+plugins: []
+
+labels: {"env":"dev \"local\""}
+
+# Database connection
+db:
+  # Connection URI
+  # Required
+  # uri:
+
+  pool:
+    size: 0
+
+# Cache settings
+# cache:
+  # ttl:
+"#;
+
+static TOML: &str = r#"# My application
+#
+# Serves things.
+
+# Name of the instance
+# Required
+# name =
+
+# Port to listen on
+port = 8080
+
+# optional =
+
+plugins = []
+
+labels = { "env" = "dev \"local\"" }
+
+# Database connection
+[db]
+# Connection URI
+# Required
+# uri =
+
+[db.pool]
+size = 0
+
+# Cache settings
+[cache]
+# ttl =
+"#;
+
+#[test]
+fn test_template_yaml() -> anyhow::Result<()> {
+    let template = Config::template(Format::Yaml)?;
+    assert_eq!(template, YAML);
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(template, Format::Yaml))
+        .new_layer(Source::String(
+            "name: test\ndb:\n  uri: test".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.labels["env"], "dev \"local\"");
+
+    Ok(())
+}
+
+#[test]
+fn test_template_toml() -> anyhow::Result<()> {
+    let template = Config::template(Format::Toml)?;
+    assert_eq!(template, TOML);
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(template, Format::Toml))
+        .new_layer(Source::String(
+            "name: test\ndb:\n  uri: test".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.labels["env"], "dev \"local\"");
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Renamed {
+    #[layered(default = "default_port")]
+    #[clap(long)]
+    listen_port: u16,
+    #[layered(subconfig)]
+    #[serde(rename = "database")]
+    db: RenamedDatabase,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+#[serde(rename_all = "camelCase")]
+struct RenamedDatabase {
+    #[layered(default)]
+    #[clap(long)]
+    pool_size: u32,
+}
+
+#[test]
+fn test_template_renamed() -> anyhow::Result<()> {
+    let yaml = Renamed::template(Format::Yaml)?;
+    assert_eq!(yaml, "listen-port: 8080\n\ndatabase:\n  poolSize: 0\n");
+    let toml = Renamed::template(Format::Toml)?;
+    assert_eq!(toml, "listen-port = 8080\n\n[database]\npoolSize = 0\n");
+
+    for (template, format) in [(yaml, Format::Yaml), (toml, Format::Toml)] {
+        let config: Renamed = Builder::new()
+            .strict(true)
+            .new_layer(Source::String(template, format))
+            .solidify()?;
+        assert_eq!(config.listen_port, 8080);
+        assert_eq!(config.db.pool_size, 0);
+    }
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Server {
+    #[layered(default = "default_port")]
+    #[clap(long)]
+    port: u16,
+    /// TLS settings
+    #[layered(subconfig)]
+    tls: Option<Tls>,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Tls {
+    #[clap(long)]
+    cert: String,
+    #[layered(default = "default_tls_port")]
+    #[clap(long)]
+    tls_port: u16,
+    #[layered(subconfig)]
+    client: TlsClient,
+}
+
+fn default_tls_port() -> u16 {
+    8443
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct TlsClient {
+    #[layered(default)]
+    #[clap(long)]
+    verify: bool,
+}
+
+#[test]
+fn test_template_optional_subconfig() -> anyhow::Result<()> {
+    // The optional section is commented out, with its defaults, as setting them enables it
+    let yaml = Server::template(Format::Yaml)?;
+    assert_eq!(
+        yaml,
+        "port: 8080\n\n# TLS settings\n# tls:\n  # Required\n  # cert:\n\n  # tls_port: 8443\n\n  \
+         # client:\n    # verify: false\n"
+    );
+    let toml = Server::template(Format::Toml)?;
+    assert_eq!(
+        toml,
+        "port = 8080\n\n# TLS settings\n# [tls]\n# Required\n# cert =\n\n# tls_port = 8443\n\n\
+         # [tls.client]\n# verify = false\n"
+    );
+
+    for (template, format) in [(yaml, Format::Yaml), (toml, Format::Toml)] {
+        let config: Server = Builder::new()
+            .strict(true)
+            .new_layer(Source::String(template, format))
+            .solidify()?;
+        assert_eq!(config.port, 8080);
+        assert!(config.tls.is_none());
+    }
+
+    Ok(())
+}
@@ -2,7 +2,9 @@ use std::{path::PathBuf, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
-use layeredconf::{Builder, Format, LayeredConf, LayeredConfMerge, LayeredConfSolidify, Source};
+use layeredconf::{
+    Builder, Format, LayeredConf, LayeredConfMerge, LayeredConfSolidify, MissingField, Source,
+};
 
 #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
 struct Config {
@@ -215,7 +217,7 @@ fn test_clap() {
     builder.new_layer(Source::String("{}".to_string(), Format::Json));
     builder.new_layer(Source::String(INTERMED_JSON.to_string(), Format::Json));
     builder.new_layer(Source::String(TOP_JSON.to_string(), Format::Json));
-    let args = vec!["test_clap", "--name", "NAME_ARG"]
+    let args = ["test_clap", "--name", "NAME_ARG"]
         .iter()
         .map(|v| v.to_string())
         .collect();
@@ -232,3 +234,330 @@ fn test_clap() {
         Duration::from_secs(50) + Duration::from_nanos(99)
     );
 }
+
+#[test]
+fn test_merge_strategies() -> anyhow::Result<()> {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    struct Config {
+        #[clap(long)]
+        replaced: Vec<String>,
+        #[layered(merge = "append")]
+        #[clap(long)]
+        appended: Vec<String>,
+        #[layered(merge = "prepend")]
+        #[clap(long)]
+        prepended: Vec<String>,
+        #[layered(merge = "union")]
+        #[clap(long)]
+        unioned: Vec<String>,
+        #[layered(merge = "union")]
+        #[clap(skip)]
+        options: HashMap<String, String>,
+    }
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(
+            json!({
+                "replaced": ["system"],
+                "appended": ["system"],
+                "prepended": ["system"],
+                "unioned": ["system", "shared"],
+                "options": {"system": "system", "shared": "system"},
+            })
+            .to_string(),
+            Format::Json,
+        ))
+        .new_layer(Source::String(
+            json!({
+                "replaced": ["user"],
+                "appended": ["user"],
+                "prepended": ["user"],
+                "unioned": ["shared", "user"],
+                "options": {"shared": "user", "user": "user"},
+            })
+            .to_string(),
+            Format::Json,
+        ))
+        .new_layer(Source::ArgumentsFrom(
+            ["test", "--appended", "cli", "--prepended", "cli"]
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+        ))
+        .solidify()?;
+
+    assert_eq!(config.replaced, vec!["user"]);
+    assert_eq!(config.appended, vec!["system", "user", "cli"]);
+    assert_eq!(config.prepended, vec!["cli", "user", "system"]);
+    assert_eq!(config.unioned, vec!["system", "shared", "user"]);
+    assert_eq!(
+        config.options,
+        HashMap::from([
+            ("system".to_string(), "system".to_string()),
+            ("shared".to_string(), "user".to_string()),
+            ("user".to_string(), "user".to_string()),
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_unset() -> anyhow::Result<()> {
+    use serde_json::json;
+
+    fn default_name() -> String {
+        "DEFAULT_NAME".to_string()
+    }
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(unset_flag)]
+    struct Config {
+        #[layered(default = "default_name")]
+        #[clap(long)]
+        name: String,
+        #[clap(long)]
+        optional: Option<String>,
+        #[clap(long)]
+        other: Option<String>,
+        #[layered(subconfig)]
+        subconfig: SubConfig,
+    }
+
+    let lowest = Source::String(
+        json!({
+            "name": "lowest",
+            "optional": "lowest",
+            "other": "lowest",
+            "subconfig": {"flibble": 1, "duration": {"secs": 1, "nanos": 0}},
+        })
+        .to_string(),
+        Format::Json,
+    );
+
+    let config: Config = Builder::new()
+        .new_layer(lowest.clone())
+        .new_layer(Source::String(
+            "_unset: [name, optional]\nother: higher\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+
+    assert_eq!(config.name, "DEFAULT_NAME");
+    assert_eq!(config.optional, None);
+    assert_eq!(config.other.as_deref(), Some("higher"));
+    assert_eq!(config.subconfig.flibble, 1);
+
+    let config: Config = Builder::new()
+        .new_layer(lowest.clone())
+        .new_layer(Source::String(
+            "subconfig:\n  _unset: [duration]\n".to_string(),
+            Format::Yaml,
+        ))
+        .new_layer(Source::String(
+            json!({"subconfig": {"duration": {"secs": 5, "nanos": 0}}}).to_string(),
+            Format::Json,
+        ))
+        .solidify()?;
+
+    assert_eq!(config.subconfig.duration, Duration::from_secs(5));
+
+    let result: layeredconf::Result<Config> = Builder::new()
+        .new_layer(lowest)
+        .new_layer(Source::ArgumentsFrom(
+            ["test", "--unset", "subconfig.flibble", "--unset", "other"]
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+        ))
+        .solidify();
+
+    match result {
+        Err(layeredconf::Error::SolidifyFailedMissing { missing }) => {
+            assert_eq!(
+                missing,
+                vec![MissingField {
+                    path: "subconfig.flibble".to_string(),
+                    flag: Some("--flibble".to_string()),
+                }]
+            );
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_unset_renamed() -> anyhow::Result<()> {
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct Config {
+        #[clap(long)]
+        pool_size: Option<u32>,
+        #[layered(subconfig)]
+        #[serde(rename = "db")]
+        database: Database,
+    }
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(subconfig)]
+    #[serde(rename_all = "kebab-case")]
+    struct Database {
+        #[clap(long)]
+        max_conns: Option<u32>,
+    }
+
+    let lower = Source::String(
+        "poolSize: 4\ndb:\n  max-conns: 16\n".to_string(),
+        Format::Yaml,
+    );
+
+    // Unset paths are the keys written in files, and `_unset` isn't renamed
+    let config: Config = Builder::new()
+        .strict(true)
+        .new_layer(lower.clone())
+        .new_layer(Source::String(
+            "_unset: [poolSize, db.max-conns]\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.pool_size, None);
+    assert_eq!(config.database.max_conns, None);
+
+    let config: Config = Builder::new()
+        .new_layer(lower.clone())
+        .new_layer(Source::String(
+            "db:\n  _unset: [max-conns]\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify()?;
+    assert_eq!(config.pool_size, Some(4));
+    assert_eq!(config.database.max_conns, None);
+
+    // Paths that aren't keys of fields are unknown keys
+    let result: layeredconf::Result<Config> = Builder::new()
+        .strict(true)
+        .new_layer(lower)
+        .new_layer(Source::String(
+            "_unset: [pool_size, database]\n".to_string(),
+            Format::Yaml,
+        ))
+        .solidify();
+    match result {
+        Err(layeredconf::Error::UnknownKeys { unknown }) => assert_eq!(
+            unknown
+                .iter()
+                .map(|key| (key.path.as_str(), key.suggestion.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![("pool_size", Some("poolSize")), ("database", None)]
+        ),
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_fields() {
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    struct Config {
+        #[clap(long)]
+        name: String,
+        #[clap(short)]
+        verbose: bool,
+        #[clap(long = "listen-port")]
+        port: u16,
+        #[clap(skip)]
+        skipped: u32,
+        #[clap(long)]
+        optional: Option<String>,
+        #[layered(subconfig)]
+        db: Database,
+    }
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(subconfig)]
+    struct Database {
+        #[clap(long)]
+        uri: String,
+        #[layered(subconfig)]
+        pool: Pool,
+    }
+
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    #[layered(subconfig)]
+    struct Pool {
+        #[clap(long)]
+        pool_size: u32,
+    }
+
+    let result: layeredconf::Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            r#"{"verbose": true}"#.to_string(),
+            Format::Json,
+        ))
+        .solidify();
+
+    let error = result.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Solidify failed, missing fields name (--name), port (--listen-port), skipped, \
+         db.uri (--uri), db.pool.pool_size (--pool-size)"
+    );
+
+    let result: layeredconf::Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            r#"{"db": {"uri": "db"}}"#.to_string(),
+            Format::Json,
+        ))
+        .solidify();
+
+    match result {
+        Err(layeredconf::Error::SolidifyFailedMissing { missing }) => {
+            assert_eq!(
+                missing
+                    .iter()
+                    .map(|field| (field.path.as_str(), field.flag.as_deref()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("name", Some("--name")),
+                    ("verbose", Some("-v")),
+                    ("port", Some("--listen-port")),
+                    ("skipped", None),
+                    ("db.pool.pool_size", Some("--pool-size")),
+                ]
+            );
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_missing_raw_identifier() {
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    struct Config {
+        #[clap(long)]
+        r#type: String,
+    }
+
+    match Builder::<Config>::new()
+        .new_layer(Source::String("{}".to_string(), Format::Json))
+        .solidify()
+    {
+        Err(layeredconf::Error::SolidifyFailedMissing { missing }) => {
+            assert_eq!(
+                missing,
+                vec![MissingField {
+                    path: "type".to_string(),
+                    flag: Some("--type".to_string()),
+                }]
+            );
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,275 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use layeredconf::{Builder, Error, Format, LayeredConf, Result, Source, ValidationFailure};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[layered(range(min = 1, max = 65535))]
+    #[clap(long)]
+    port: u32,
+    #[layered(range(min = "-10"))]
+    #[clap(long)]
+    offset: Option<i32>,
+    #[layered(non_empty)]
+    #[clap(long)]
+    name: String,
+    #[layered(path_exists)]
+    #[clap(long)]
+    data_path: Option<PathBuf>,
+    #[layered(validate = "validate_url", non_empty)]
+    #[clap(long)]
+    url: String,
+    #[layered(subconfig)]
+    db: Database,
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig)]
+struct Database {
+    #[layered(non_empty)]
+    #[clap(long)]
+    hosts: Vec<String>,
+}
+
+fn validate_url(url: &str) -> std::result::Result<(), String> {
+    if url.contains("://") {
+        Ok(())
+    } else {
+        Err(format!("{:?} isn't a URL", url))
+    }
+}
+
+fn solidify(value: serde_json::Value) -> Result<Config> {
+    Builder::new()
+        .new_layer(Source::String(value.to_string(), Format::Json))
+        .solidify()
+}
+
+#[test]
+fn test_validate() -> anyhow::Result<()> {
+    let config = solidify(json!({
+        "port": 80,
+        "offset": -10,
+        "name": "name",
+        "data_path": "tests",
+        "url": "http://localhost",
+        "db": {"hosts": ["localhost"]},
+    }))?;
+    assert_eq!(config.port, 80);
+
+    let config = solidify(json!({
+        "port": 65535,
+        "name": "name",
+        "url": "http://localhost",
+        "db": {"hosts": ["localhost"]},
+    }))?;
+    assert_eq!(config.offset, None);
+    assert_eq!(config.data_path, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_failures() {
+    let result = solidify(json!({
+        "port": 0,
+        "offset": -11,
+        "name": "",
+        "data_path": "tests/does_not_exist",
+        "url": "localhost",
+        "db": {"hosts": []},
+    }));
+
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(
+                failures
+                    .iter()
+                    .map(|failure| (failure.path.as_str(), failure.message.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("port", "must be between 1 and 65535, got 0"),
+                    ("offset", "must be at least -10, got -11"),
+                    ("name", "must not be empty"),
+                    ("data_path", "path \"tests/does_not_exist\" doesn't exist"),
+                    ("url", "\"localhost\" isn't a URL"),
+                    ("db.hosts", "must not be empty"),
+                ]
+            );
+            // Every value was set by the single line JSON string
+            assert!(failures.iter().all(|failure| failure
+                .location
+                .as_ref()
+                .is_some_and(|location| location.line == Some(1))));
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_validate_error_message() {
+    let error = solidify(json!({
+        "port": 100000,
+        "name": "name",
+        "url": "",
+        "db": {"hosts": ["localhost"]},
+    }))
+    .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Validation failed, port: must be between 1 and 65535, got 100000 at <string>:1:46, \
+         url: must not be empty at <string>:1:60, url: \"\" isn't a URL at <string>:1:60"
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_validate_regex() -> anyhow::Result<()> {
+    #[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+    struct Config {
+        #[layered(regex = "^[a-z]+$")]
+        #[clap(long)]
+        name: String,
+    }
+
+    let config: Config = Builder::new()
+        .new_layer(Source::String(
+            r#"{"name": "abc"}"#.to_string(),
+            Format::Json,
+        ))
+        .solidify()?;
+    assert_eq!(config.name, "abc");
+
+    let result: Result<Config> = Builder::new()
+        .new_layer(Source::String(
+            r#"{"name": "ABC"}"#.to_string(),
+            Format::Json,
+        ))
+        .solidify();
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(failures.len(), 1);
+            assert_eq!(
+                failures[0].to_string(),
+                "name: \"ABC\" doesn't match pattern \"^[a-z]+$\" at <string>:1:3"
+            );
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(validate_with = "validate_limits")]
+struct Limits {
+    #[layered(range(max = 100))]
+    #[clap(long)]
+    min: u32,
+    #[clap(long)]
+    max: u32,
+    #[layered(subconfig)]
+    tls: Tls,
+}
+
+fn validate_limits(limits: &Limits) -> std::result::Result<(), String> {
+    if limits.min <= limits.max {
+        Ok(())
+    } else {
+        Err(format!(
+            "min {} is more than max {}",
+            limits.min, limits.max
+        ))
+    }
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+#[layered(subconfig, validate_with = "validate_tls")]
+struct Tls {
+    #[clap(long)]
+    enabled: bool,
+    #[clap(long)]
+    cert: Option<PathBuf>,
+}
+
+fn validate_tls(tls: &Tls) -> std::result::Result<(), &'static str> {
+    if tls.enabled && tls.cert.is_none() {
+        Err("cert must be set when enabled")
+    } else {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_validate_with() -> anyhow::Result<()> {
+    let limits: Limits = Builder::new()
+        .new_layer(Source::String(
+            json!({"min": 1, "max": 2, "tls": {"enabled": true, "cert": "cert.pem"}}).to_string(),
+            Format::Json,
+        ))
+        .solidify()?;
+    assert_eq!(limits.max, 2);
+
+    let result: Result<Limits> = Builder::new()
+        .new_layer(Source::String(
+            json!({"min": 200, "max": 2, "tls": {"enabled": true}}).to_string(),
+            Format::Json,
+        ))
+        .solidify();
+
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(
+                failures
+                    .iter()
+                    .map(ValidationFailure::to_string)
+                    .collect::<Vec<_>>(),
+                vec![
+                    "min: must be at most 100, got 200 at <string>:1:11",
+                    "tls: cert must be set when enabled",
+                    "min 200 is more than max 2",
+                ]
+            );
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Retries {
+    #[layered(default, range(min = 1))]
+    #[clap(long)]
+    retries: u32,
+}
+
+#[test]
+fn test_validate_unset_location() {
+    let lower = Source::String("{\"retries\": 0}".to_string(), Format::Json);
+
+    let result: Result<Retries> = Builder::new().new_layer(lower.clone()).solidify();
+    match result {
+        Err(Error::ValidationFailed { failures }) => {
+            assert_eq!(failures[0].location.as_ref().unwrap().line, Some(1))
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    // The invalid value is the default, which no Layer set
+    let result: Result<Retries> = Builder::new()
+        .new_layer(lower)
+        .new_layer(Source::String(
+            "{\"_unset\": [\"retries\"]}".to_string(),
+            Format::Json,
+        ))
+        .solidify();
+    match result {
+        Err(Error::ValidationFailed { failures }) => assert_eq!(failures[0].location, None),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
@@ -0,0 +1,134 @@
+use std::{fs, path::Path, sync::mpsc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use layeredconf::{Builder, Format, LayeredConf, Result, Source};
+
+#[derive(LayeredConf, Deserialize, Serialize, Clone, Debug)]
+struct Config {
+    #[layered(load_config)]
+    #[clap(long)]
+    config: Option<std::path::PathBuf>,
+    #[clap(long)]
+    name: String,
+    #[layered(default)]
+    #[clap(long)]
+    port: u16,
+}
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn write(path: &Path, contents: &str) {
+    // Write then rename, as editors do, so the watcher never sees a half written file
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents).unwrap();
+    fs::rename(&tmp, path).unwrap();
+}
+
+#[test]
+fn test_watch() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config.yaml");
+    write(&path, "name: first");
+
+    let (sender, receiver) = mpsc::channel::<Result<_>>();
+    let watch = Builder::<Config>::new()
+        .new_layer(Source::File(path.clone(), Format::Auto))
+        .watch(move |result| sender.send(result).unwrap())?;
+
+    assert_eq!(watch.current().name, "first");
+
+    write(&path, "name: second\nport: 80");
+    let config = receiver.recv_timeout(TIMEOUT)??;
+    assert_eq!(config.name, "second");
+    assert_eq!(config.port, 80);
+    assert_eq!(watch.current().name, "second");
+
+    write(&path, "name: [not, a, string]");
+    assert!(receiver.recv_timeout(TIMEOUT)?.is_err());
+    assert_eq!(watch.current().name, "second");
+
+    write(&path, "name: third");
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.name, "third");
+    assert_eq!(watch.current().name, "third");
+
+    Ok(())
+}
+
+#[test]
+fn test_watch_load_config() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("sub"))?;
+    let path = dir.path().join("config.yaml");
+    let sub_path = dir.path().join("sub/extra.yaml");
+    write(&path, "name: top");
+    write(&sub_path, "port: 1");
+
+    let (sender, receiver) = mpsc::channel::<Result<_>>();
+    let watch = Builder::<Config>::new()
+        .new_layer(Source::File(path.clone(), Format::Auto))
+        .watch(move |result| sender.send(result).unwrap())?;
+
+    assert_eq!(watch.current().port, 0);
+
+    write(&path, "name: top\nconfig: sub/extra.yaml");
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.port, 1);
+
+    write(&sub_path, "port: 2");
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.port, 2);
+    assert_eq!(watch.current().port, 2);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_watch_symlink() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("real"))?;
+    let target = dir.path().join("real/config.yaml");
+    let link = dir.path().join("config.yaml");
+    write(&target, "name: first");
+    std::os::unix::fs::symlink(&target, &link)?;
+
+    let (sender, receiver) = mpsc::channel::<Result<_>>();
+    let watch = Builder::<Config>::new()
+        .new_layer(Source::File(
+            dir.path().join("real/../config.yaml"),
+            Format::Auto,
+        ))
+        .watch(move |result| sender.send(result).unwrap())?;
+
+    assert_eq!(watch.current().name, "first");
+
+    write(&target, "name: second");
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.name, "second");
+    assert_eq!(watch.current().name, "second");
+
+    Ok(())
+}
+
+#[test]
+fn test_watch_missing_dir() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let sub = dir.path().join("conf.d");
+    let path = sub.join("config.yaml");
+
+    let (sender, receiver) = mpsc::channel::<Result<_>>();
+    let watch = Builder::<Config>::new()
+        .new_layer(Source::String("name: first".to_string(), Format::Yaml))
+        .new_layer(Source::FileOptional(path.clone(), Format::Auto))
+        .watch(move |result| sender.send(result).unwrap())?;
+
+    assert_eq!(watch.current().name, "first");
+
+    // Creating the directory reloads, and watches it for the file
+    fs::create_dir(&sub)?;
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.name, "first");
+
+    write(&path, "name: second");
+    assert_eq!(receiver.recv_timeout(TIMEOUT)??.name, "second");
+    assert_eq!(watch.current().name, "second");
+
+    Ok(())
+}